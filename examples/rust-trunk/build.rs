@@ -14,6 +14,37 @@ fn main() {
 
     println!("cargo:rustc-link-search=native={}", lib_dir.display());
 
+    // `thread-safe` makes `Expr` Sync, which is only sound if SymEngine's
+    // RCP reference counts are atomic. Check the installed config header.
+    if env::var_os("CARGO_FEATURE_THREAD_SAFE").is_some() {
+        let include_dir = if let Ok(dir) = env::var("SYMENGINE_INCLUDE_DIR") {
+            PathBuf::from(dir)
+        } else {
+            lib_dir.join("../include")
+        };
+        let config = include_dir.join("symengine/symengine_config.h");
+        let header = std::fs::read_to_string(&config).unwrap_or_else(|e| {
+            panic!(
+                "feature `thread-safe` needs {} to verify the SymEngine build: {}",
+                config.display(),
+                e
+            )
+        });
+        let thread_safe = header.lines().any(|line| {
+            let mut words = line.split_whitespace();
+            words.next() == Some("#define") && words.next() == Some("WITH_SYMENGINE_THREAD_SAFE")
+        });
+        if !thread_safe {
+            panic!(
+                "feature `thread-safe` requires SymEngine built with \
+                 -DWITH_SYMENGINE_THREAD_SAFE=ON, but {} does not define it",
+                config.display()
+            );
+        }
+        println!("cargo:rerun-if-env-changed=SYMENGINE_INCLUDE_DIR");
+        println!("cargo:rerun-if-changed={}", config.display());
+    }
+
     // Link order matters: symengine first, then C++ runtime, then C runtime
     println!("cargo:rustc-link-lib=static=symengine");
 
@@ -104,12 +104,31 @@ pub unsafe extern "C" fn __libc_calloc(nmemb: usize, size: usize) -> *mut u8 {
 // wasm-bindgen exports
 // ---------------------------------------------------------------------------
 
+/// Parse user input, turning a parse failure into a thrown JS error that
+/// names the offending input.
+fn parse(s: &str) -> Result<symengine::Expr, JsError> {
+    symengine::Expr::try_parse(s).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Build the symbol named by user input, such as a differentiation variable.
+fn parse_symbol(name: &str) -> Result<symengine::Expr, JsError> {
+    symengine::Expr::try_symbol(name)
+        .map_err(|e| JsError::new(&format!("invalid symbol name {:?}: {}", name, e)))
+}
+
 /// Generate a #[wasm_bindgen] unary export: parse expr, call method, return string.
+/// An optional third argument sets the name seen from JavaScript.
 macro_rules! wasm_unary {
     ($name:ident, $method:ident) => {
         #[wasm_bindgen]
-        pub fn $name(expr: &str) -> String {
-            symengine::Expr::parse(expr).$method().to_string()
+        pub fn $name(expr: &str) -> Result<String, JsError> {
+            Ok(parse(expr)?.$method().to_string())
+        }
+    };
+    ($name:ident, $method:ident, $js:ident) => {
+        #[wasm_bindgen(js_name = $js)]
+        pub fn $name(expr: &str) -> Result<String, JsError> {
+            Ok(parse(expr)?.$method().to_string())
         }
     };
 }
@@ -118,21 +137,173 @@ macro_rules! wasm_unary {
 macro_rules! wasm_binary {
     ($name:ident, $method:ident) => {
         #[wasm_bindgen]
-        pub fn $name(a: &str, b: &str) -> String {
-            symengine::Expr::parse(a)
-                .$method(&symengine::Expr::parse(b))
-                .to_string()
+        pub fn $name(a: &str, b: &str) -> Result<String, JsError> {
+            Ok(parse(a)?.$method(&parse(b)?).to_string())
+        }
+    };
+}
+
+/// Parse a JSON array of expression strings, e.g. `["x + y", "2*x"]`.
+fn parse_json_list(json: &str) -> Result<Vec<symengine::Expr>, JsError> {
+    let items: Vec<String> = serde_json::from_str(json)
+        .map_err(|e| JsError::new(&format!("expected a JSON array of strings: {}", e)))?;
+    items.iter().map(|s| parse(s)).collect()
+}
+
+/// Reject anything that is not a plain symbol (e.g. as a differentiation
+/// variable), which SymEngine cannot handle.
+fn check_symbols(syms: &[symengine::Expr]) -> Result<(), JsError> {
+    match syms.iter().find(|s| !s.is_symbol()) {
+        Some(s) => Err(JsError::new(&format!("'{}' is not a symbol", s))),
+        None => Ok(()),
+    }
+}
+
+/// Encode expressions as a JSON array of their string forms.
+fn to_json_list(exprs: &[symengine::Expr]) -> String {
+    let items: Vec<String> = exprs.iter().map(|e| e.to_string()).collect();
+    serde_json::to_string(&items).unwrap()
+}
+
+/// Encode a matrix as JSON nested arrays of entry strings, row-major: the
+/// outer array holds rows, so `result[r][c]` is the entry in row r, column c.
+/// Each entry is an ordinary expression string that `parse` accepts again.
+/// Every row is emitted even when empty, so an n×0 matrix is n empty arrays
+/// (`[[]]` is 1×0) and a 0×n matrix is `[]`.
+fn matrix_to_json(m: &symengine::Matrix) -> String {
+    serde_json::to_string(&matrix_rows(m)).unwrap()
+}
+
+/// Entry strings of a matrix as a vector of rows, for embedding in larger
+/// JSON results.
+fn matrix_rows(m: &symengine::Matrix) -> Vec<Vec<String>> {
+    m.iter_rows()
+        .map(|row| row.iter().map(|e| e.to_string()).collect())
+        .collect()
+}
+
+/// Parse comma-separated expressions. Only top-level commas separate
+/// entries, so `"atan2(y, x), 1"` has two; blank input is an empty list,
+/// but a blank entry such as the middle of `"x,,y"` is an error.
+fn parse_csv(csv: &str) -> Result<Vec<symengine::Expr>, JsError> {
+    if csv.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in csv.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                entries.push(&csv[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    entries.push(&csv[start..]);
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, s)| match s.trim() {
+            "" => Err(JsError::new(&format!("entry {} of the list is empty", i + 1))),
+            s => parse(s),
+        })
+        .collect()
+}
+
+/// Parse a list given either as a JSON array of strings or comma-separated.
+fn parse_list(list: &str) -> Result<Vec<symengine::Expr>, JsError> {
+    if list.trim_start().starts_with('[') {
+        parse_json_list(list)
+    } else {
+        parse_csv(list)
+    }
+}
+
+/// Generate a #[wasm_bindgen] predicate export: parse expr, return a bool.
+macro_rules! wasm_predicate {
+    ($name:ident, $method:ident) => {
+        #[wasm_bindgen]
+        pub fn $name(expr: &str) -> Result<bool, JsError> {
+            Ok(parse(expr)?.$method())
         }
     };
 }
 
+/// Parse a JSON nested array of expression strings, e.g. `[["a","b"],["c","d"]]`,
+/// into a Matrix. The shape comes from the array itself and must be rectangular.
+fn parse_json_matrix(json: &str) -> Result<symengine::Matrix, JsError> {
+    let rows: Vec<Vec<String>> = serde_json::from_str(json).map_err(|e| {
+        JsError::new(&format!("expected a JSON array of arrays of strings: {}", e))
+    })?;
+    if rows.first().is_none_or(Vec::is_empty) {
+        return Err(JsError::new("matrix must have at least one row and one column"));
+    }
+    let rows = rows
+        .iter()
+        .map(|row| row.iter().map(|s| parse(s)).collect())
+        .collect::<Result<Vec<Vec<_>>, JsError>>()?;
+    symengine::Matrix::from_nested(&rows).map_err(|e| JsError::new(&e.to_string()))
+}
+
+const MATRIX_OPS: &str = "expand, neg, abs, sqrt, exp, log, sin, cos, tan, evalf";
+
+/// Element-wise operation named by `op`, for `matrix_apply`.
+fn matrix_op(op: &str) -> Result<fn(&symengine::Expr) -> symengine::Expr, JsError> {
+    use symengine::Expr;
+    Ok(match op {
+        "expand" => Expr::expand,
+        "neg" => Expr::neg,
+        "abs" => Expr::abs,
+        "sqrt" => Expr::sqrt,
+        "exp" => Expr::exp,
+        "log" => Expr::log,
+        "sin" => Expr::sin,
+        "cos" => Expr::cos,
+        "tan" => Expr::tan,
+        "evalf" => |e| e.evalf(53, false),
+        _ => {
+            return Err(JsError::new(&format!(
+                "unknown operation '{}'; expected one of: {}",
+                op, MATRIX_OPS
+            )))
+        }
+    })
+}
+
+/// Reject empty shapes for the matrix constructors.
+fn check_shape(rows: u32, cols: u32) -> Result<(), JsError> {
+    if rows == 0 || cols == 0 {
+        return Err(JsError::new("matrix must have at least one row and one column"));
+    }
+    Ok(())
+}
+
+/// Reject a (row, column) position outside the matrix.
+fn check_position(m: &symengine::Matrix, r: u32, c: u32) -> Result<(), JsError> {
+    if r >= m.rows() || c >= m.cols() {
+        return Err(JsError::new(&format!(
+            "position ({}, {}) is outside the {}x{} matrix",
+            r,
+            c,
+            m.rows(),
+            m.cols()
+        )));
+    }
+    Ok(())
+}
+
+/// Error mapper for a failed matrix operation, e.g. `matrix_err("invert matrix")`.
+fn matrix_err(op: &'static str) -> impl Fn(symengine::MatrixError) -> JsError {
+    move |e| JsError::new(&format!("cannot {}: {}", op, e))
+}
+
 /// Parse comma-separated expressions into a Matrix.
-fn parse_matrix(rows: u32, cols: u32, csv: &str) -> symengine::Matrix {
-    let elems: Vec<symengine::Expr> = csv
-        .split(',')
-        .map(|s| symengine::Expr::parse(s.trim()))
-        .collect();
-    symengine::Matrix::from_vec(rows, cols, &elems)
+fn parse_matrix(rows: u32, cols: u32, csv: &str) -> Result<symengine::Matrix, JsError> {
+    symengine::Matrix::from_vec(rows, cols, &parse_csv(csv)?)
+        .map_err(|e| JsError::new(&e.to_string()))
 }
 
 // ===================== Version =====================
@@ -142,40 +313,419 @@ pub fn symengine_version_str() -> String {
     symengine::version_str()
 }
 
+// ===================== Constants =====================
+
+/// Names accepted by `constant` / `constant_evalf`.
+const CONSTANT_NAMES: &str =
+    "pi, e, euler_gamma, catalan, golden_ratio, i, oo, -oo, zoo, nan";
+
+fn named_constant(name: &str) -> Result<symengine::Expr, JsError> {
+    use symengine::Expr;
+    Ok(match name {
+        "pi" => Expr::pi(),
+        "e" => Expr::e_constant(),
+        "euler_gamma" => Expr::euler_gamma(),
+        "catalan" => Expr::catalan(),
+        "golden_ratio" => Expr::golden_ratio(),
+        "i" => Expr::imaginary_unit(),
+        "oo" => Expr::infinity(),
+        "-oo" => Expr::neg_infinity(),
+        "zoo" => Expr::complex_infinity(),
+        "nan" => Expr::nan(),
+        _ => {
+            return Err(JsError::new(&format!(
+                "unknown constant '{}' (expected one of: {})",
+                name, CONSTANT_NAMES
+            )))
+        }
+    })
+}
+
+/// Create symbols from a list of names, e.g. make_symbols("x y z") or
+/// make_symbols("q0:3"). Returns a JSON array of the symbol names.
+#[wasm_bindgen]
+pub fn make_symbols(names: &str) -> Result<String, JsError> {
+    let syms = symengine::Expr::symbols(names).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(to_json_list(&syms))
+}
+
+/// Undefined function of the given arguments (a JSON array of strings),
+/// e.g. func("f", '["x", "y"]') → "f(x, y)".
+#[wasm_bindgen]
+pub fn func(name: &str, args_json: &str) -> Result<String, JsError> {
+    let args = parse_json_list(args_json)?;
+    symengine::Expr::try_function(name, &args)
+        .map(|f| f.to_string())
+        .map_err(|_| JsError::new(&format!("invalid function name '{}'", name)))
+}
+
+/// Build `re + im*I` from two expressions, e.g. complex("3", "-4") → "3 - 4*I".
+#[wasm_bindgen]
+pub fn complex(re: &str, im: &str) -> Result<String, JsError> {
+    Ok(symengine::Expr::complex(&parse(re)?, &parse(im)?).to_string())
+}
+
+/// Canonical SymEngine spelling of a named constant, e.g. constant("e") → "E".
+#[wasm_bindgen]
+pub fn constant(name: &str) -> Result<String, JsError> {
+    Ok(named_constant(name)?.to_string())
+}
+
+/// Numeric value of a named constant to `bits` bits of precision.
+#[wasm_bindgen]
+pub fn constant_evalf(name: &str, bits: u32) -> Result<String, JsError> {
+    check_evalf_bits(bits)?;
+    Ok(named_constant(name)?.evalf(bits, false).to_string())
+}
+
+// ===================== Parsing =====================
+
+/// Parse with `caret_is_pow` controlling whether `^` means exponentiation.
+fn parse_caret(expr: &str, caret_is_pow: bool) -> Result<symengine::Expr, JsError> {
+    symengine::Expr::parse_with_options(expr, caret_is_pow)
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Canonical form of `expr`, e.g. parse_expr("x^2 + 1", true) → "1 + x**2".
+#[wasm_bindgen]
+pub fn parse_expr(expr: &str, caret_is_pow: bool) -> Result<String, JsError> {
+    Ok(parse_caret(expr, caret_is_pow)?.to_string())
+}
+
+#[wasm_bindgen]
+pub fn expand_with(expr: &str, caret_is_pow: bool) -> Result<String, JsError> {
+    Ok(parse_caret(expr, caret_is_pow)?.expand().to_string())
+}
+
+#[wasm_bindgen]
+pub fn differentiate_with(expr: &str, var: &str, caret_is_pow: bool) -> Result<String, JsError> {
+    let v = parse_symbol(var)?;
+    Ok(parse_caret(expr, caret_is_pow)?.diff(&v).to_string())
+}
+
+/// Like `evalf`, with a choice of how `^` is parsed.
+#[wasm_bindgen]
+pub fn evalf_with(expr: &str, caret_is_pow: bool) -> Result<String, JsError> {
+    try_evalf(&parse_caret(expr, caret_is_pow)?, 53, false)
+}
+
 // ===================== Core operations =====================
 
 wasm_unary!(expand, expand);
 
 #[wasm_bindgen]
-pub fn differentiate(expr: &str, var: &str) -> String {
-    let e = symengine::Expr::parse(expr);
-    let v = symengine::Expr::symbol(var);
-    e.diff(&v).to_string()
+pub fn differentiate(expr: &str, var: &str) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let v = parse_symbol(var)?;
+    Ok(e.diff(&v).to_string())
+}
+
+/// JSON array of partial derivatives in the order of `vars_json`. An empty
+/// `vars_json` (or `[]`) uses the sorted free symbols of the expression.
+#[wasm_bindgen]
+pub fn gradient(expr: &str, vars_json: &str) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let mut vars = if vars_json.trim().is_empty() {
+        Vec::new()
+    } else {
+        parse_json_list(vars_json)?
+    };
+    if vars.is_empty() {
+        let mut names = e.free_symbols();
+        names.sort();
+        vars = names.iter().map(|n| symengine::Expr::symbol(n)).collect();
+    }
+    check_symbols(&vars)?;
+    Ok(to_json_list(&e.gradient(&vars)))
+}
+
+#[wasm_bindgen]
+pub fn differentiate_n(expr: &str, var: &str, n: u32) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let v = parse_symbol(var)?;
+    Ok(e.diff_n(&v, n).to_string())
+}
+
+/// Mixed partial derivative. `orders_json` maps each variable to its order,
+/// e.g. `{"x": 2, "y": 1}` for ∂³/∂x²∂y.
+#[wasm_bindgen]
+pub fn differentiate_multi(expr: &str, orders_json: &str) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let orders: serde_json::Map<String, serde_json::Value> = serde_json::from_str(orders_json)
+        .map_err(|err| JsError::new(&format!("expected a JSON object of orders: {}", err)))?;
+    let mut syms = Vec::with_capacity(orders.len());
+    for (name, n) in &orders {
+        let n = n
+            .as_u64()
+            .and_then(|n| u32::try_from(n).ok())
+            .ok_or_else(|| JsError::new(&format!("order for '{}' must be a non-negative integer", name)))?;
+        syms.push((parse(name)?, n));
+    }
+    let vars: Vec<_> = syms.iter().map(|(s, _)| s.clone()).collect();
+    check_symbols(&vars)?;
+    let pairs: Vec<_> = syms.iter().map(|(s, n)| (s, *n)).collect();
+    e.diff_multi(&pairs)
+        .map(|d| d.to_string())
+        .map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// Taylor polynomial of `expr` in `var` around `about`, up to `order`.
+/// Removable singularities such as `sin(x)/x` at 0 are allowed; a pole at
+/// `about` throws.
+#[wasm_bindgen]
+pub fn taylor(expr: &str, var: &str, about: &str, order: u32) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let v = parse_symbol(var)?;
+    let a = parse(about)?;
+    e.try_taylor(&v, &a, order)
+        .map(|t| t.to_string())
+        .map_err(|err| JsError::new(&format!("cannot expand {} about {} = {}: {}", e, var, a, err)))
+}
+
+#[wasm_bindgen]
+pub fn substitute(expr: &str, var: &str, value: &str) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let from = parse_symbol(var)?;
+    let to = parse(value)?;
+    Ok(e.subs(&from, &to).to_string())
+}
+
+/// Parse a JSON object of replacements, e.g. `{"x": "y", "y": "x"}`, into
+/// (from, to) pairs.
+fn parse_subs_map(map_json: &str) -> Result<Vec<(symengine::Expr, symengine::Expr)>, JsError> {
+    let map: std::collections::BTreeMap<String, String> = serde_json::from_str(map_json)
+        .map_err(|e| JsError::new(&format!("expected a JSON object of strings: {}", e)))?;
+    map.iter().map(|(k, v)| Ok((parse(k)?, parse(v)?))).collect()
+}
+
+/// Simultaneous substitution from a JSON object, e.g. `{"x": "y", "y": "x"}`.
+/// All replacements are applied at once, so overlapping pairs don't interfere.
+#[wasm_bindgen]
+pub fn substitute_many(expr: &str, map_json: &str) -> Result<String, JsError> {
+    let pairs = parse_subs_map(map_json)?;
+    let refs: Vec<(&symengine::Expr, &symengine::Expr)> =
+        pairs.iter().map(|(k, v)| (k, v)).collect();
+    Ok(parse(expr)?.subs_map(&refs).to_string())
+}
+
+/// Numeric value as a double. Throws if symbols remain or the value is
+/// undefined, e.g. `log(0)`.
+#[wasm_bindgen]
+pub fn evalf(expr: &str) -> Result<String, JsError> {
+    try_evalf(&parse(expr)?, 53, false)
+}
+
+/// `Expr::try_evalf`, with the error naming the expression.
+fn try_evalf(e: &symengine::Expr, bits: u32, real_only: bool) -> Result<String, JsError> {
+    e.try_evalf(bits, real_only)
+        .map(|v| v.to_string())
+        .map_err(|err| JsError::new(&format!("cannot evaluate {}: {}", e, err)))
+}
+
+/// Evaluate at numeric values from a JSON object, e.g. `{"x": 1.5, "y": -2}`.
+/// Every free symbol must have a value.
+#[wasm_bindgen]
+pub fn eval_at(expr: &str, bindings_json: &str) -> Result<f64, JsError> {
+    let e = parse(expr)?;
+    let map: std::collections::BTreeMap<String, f64> = serde_json::from_str(bindings_json)
+        .map_err(|err| JsError::new(&format!("expected a JSON object of numbers: {}", err)))?;
+    if let Some(name) = e.free_symbols().into_iter().find(|s| !map.contains_key(s)) {
+        return Err(JsError::new(&format!("no value given for {}", name)));
+    }
+    let syms = map
+        .iter()
+        .map(|(k, v)| Ok((parse(k)?, *v)))
+        .collect::<Result<Vec<_>, JsError>>()?;
+    let bindings: Vec<_> = syms.iter().map(|(k, v)| (k, *v)).collect();
+    e.eval_at(&bindings)
+        .map_err(|err| JsError::new(&format!("cannot evaluate {}: {}", e, err)))
+}
+
+/// Parse an expression that must be free of symbols so it can be evaluated.
+fn parse_numeric(expr: &str) -> Result<symengine::Expr, JsError> {
+    let e = parse(expr)?;
+    let syms = e.free_symbols();
+    if !syms.is_empty() {
+        return Err(JsError::new(&format!(
+            "cannot evaluate numerically: free symbols {}",
+            syms.join(", ")
+        )));
+    }
+    Ok(e)
+}
+
+/// Error unless every free symbol of `e` is one of `allowed`.
+fn check_free_symbols(e: &symengine::Expr, allowed: &[&str]) -> Result<(), JsError> {
+    let extra: Vec<String> = e
+        .free_symbols()
+        .into_iter()
+        .filter(|s| !allowed.contains(&s.as_str()))
+        .collect();
+    if !extra.is_empty() {
+        return Err(JsError::new(&format!(
+            "cannot evaluate numerically: unbound symbols {}",
+            extra.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Substitute real values, then evaluate; complex or non-finite results
+/// become NaN.
+fn eval_point(e: &symengine::Expr, bindings: &[(&symengine::Expr, f64)]) -> f64 {
+    let values: Vec<symengine::Expr> = bindings
+        .iter()
+        .map(|(_, v)| symengine::Expr::real_double(*v))
+        .collect();
+    let pairs: Vec<(&symengine::Expr, &symengine::Expr)> =
+        bindings.iter().map(|(s, _)| *s).zip(values.iter()).collect();
+    e.subs_map(&pairs)
+        .to_f64()
+        .filter(|v| v.is_finite())
+        .unwrap_or(f64::NAN)
+}
+
+/// Evaluate `expr` at each of `values` for `var`, returning a Float64Array.
+#[wasm_bindgen]
+pub fn eval_batch(expr: &str, var: &str, values: &[f64]) -> Result<Vec<f64>, JsError> {
+    let e = parse(expr)?;
+    check_free_symbols(&e, &[var])?;
+    let x = parse_symbol(var)?;
+    Ok(values.iter().map(|&v| eval_point(&e, &[(&x, v)])).collect())
+}
+
+/// Evaluate a two-variable `expr` at flattened `[x0, y0, x1, y1, ...]` pairs.
+#[wasm_bindgen]
+pub fn eval_batch_multi(
+    expr: &str, var_x: &str, var_y: &str, pairs: &[f64],
+) -> Result<Vec<f64>, JsError> {
+    let points = pairs.chunks_exact(2);
+    if !points.remainder().is_empty() {
+        return Err(JsError::new("pairs must contain an even number of values"));
+    }
+    let e = parse(expr)?;
+    check_free_symbols(&e, &[var_x, var_y])?;
+    let x = parse_symbol(var_x)?;
+    let y = parse_symbol(var_y)?;
+    Ok(points
+        .map(|p| eval_point(&e, &[(&x, p[0]), (&y, p[1])]))
+        .collect())
+}
+
+/// Numerical evaluation returned as a JS number.
+#[wasm_bindgen]
+pub fn eval_double(expr: &str) -> Result<f64, JsError> {
+    parse_numeric(expr)?
+        .to_f64()
+        .ok_or_else(|| JsError::new("expression does not evaluate to a real number"))
+}
+
+/// Numerical evaluation returned as a two-element array `[re, im]`.
+#[wasm_bindgen]
+pub fn eval_complex(expr: &str) -> Result<Vec<f64>, JsError> {
+    let (re, im) = parse_numeric(expr)?
+        .as_real_imag()
+        .ok_or_else(|| JsError::new("expression does not evaluate to a number"))?;
+    Ok(vec![re, im])
+}
+
+/// Largest precision `evalf` can honour: build_wasm.sh builds SymEngine
+/// without MPFR/MPC, so only machine doubles are available.
+const MAX_EVALF_BITS: u32 = 53;
+
+fn check_evalf_bits(bits: u32) -> Result<(), JsError> {
+    if bits == 0 || bits > MAX_EVALF_BITS {
+        return Err(JsError::new(&format!(
+            "unsupported precision of {} bits: this build has no MPFR, \
+             so precision must be between 1 and {}",
+            bits, MAX_EVALF_BITS
+        )));
+    }
+    Ok(())
+}
+
+/// Numerical evaluation with explicit precision and domain. With `real_only`
+/// a non-real result throws; otherwise it comes back complex. Like `evalf`,
+/// undefined values and leftover symbols throw.
+#[wasm_bindgen]
+pub fn evalf_prec(expr: &str, bits: u32, real_only: bool) -> Result<String, JsError> {
+    check_evalf_bits(bits)?;
+    try_evalf(&parse(expr)?, bits, real_only)
 }
 
 #[wasm_bindgen]
-pub fn substitute(expr: &str, var: &str, value: &str) -> String {
-    let e = symengine::Expr::parse(expr);
-    let from = symengine::Expr::symbol(var);
-    let to = symengine::Expr::parse(value);
-    e.subs(&from, &to).to_string()
+pub fn free_symbols(expr: &str) -> Result<String, JsError> {
+    Ok(parse(expr)?.free_symbols().join(", "))
 }
 
 #[wasm_bindgen]
-pub fn evalf(expr: &str) -> String {
-    symengine::Expr::parse(expr).evalf(53).to_string()
+pub fn solve_poly(expr: &str, var: &str) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let v = parse_symbol(var)?;
+    Ok(e.solve_poly(&v).join(", "))
 }
 
+/// Like `solve_poly`, but returns a JSON array of solutions (`[]` if none).
 #[wasm_bindgen]
-pub fn free_symbols(expr: &str) -> String {
-    symengine::Expr::parse(expr).free_symbols().join(", ")
+pub fn solve_poly_json(expr: &str, var: &str) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let v = parse_symbol(var)?;
+    Ok(serde_json::to_string(&e.solve_poly(&v)).unwrap())
 }
 
+/// Numeric root of `expr = 0` in `var` by Newton's method from `x0`.
 #[wasm_bindgen]
-pub fn solve_poly(expr: &str, var: &str) -> String {
-    let e = symengine::Expr::parse(expr);
-    let v = symengine::Expr::symbol(var);
-    e.solve_poly(&v).join(", ")
+pub fn newton_solve(
+    expr: &str,
+    var: &str,
+    x0: f64,
+    tol: f64,
+    max_iter: u32,
+) -> Result<f64, JsError> {
+    let e = parse(expr)?;
+    let v = parse_symbol(var)?;
+    symengine::newton_solve(&e, &v, x0, tol, max_iter)
+        .map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// Solve a linear system. Both arguments are JSON arrays of strings; the
+/// equations are taken as `lhs = 0`. Returns a JSON array of solutions in
+/// the order of `symbols_json`.
+#[wasm_bindgen]
+pub fn linsolve(equations_json: &str, symbols_json: &str) -> Result<String, JsError> {
+    let eqs = parse_json_list(equations_json)?;
+    let syms = parse_json_list(symbols_json)?;
+    if eqs.is_empty() || eqs.len() != syms.len() {
+        return Err(JsError::new(&format!(
+            "expected as many equations as symbols, got {} equations and {} symbols",
+            eqs.len(),
+            syms.len()
+        )));
+    }
+    check_symbols(&syms)?;
+    // Reject non-linear and singular systems up front: SymEngine would
+    // otherwise divide by zero while eliminating.
+    let mut coeffs = Vec::with_capacity(eqs.len() * syms.len());
+    for eq in &eqs {
+        for s in &syms {
+            let c = eq.diff(s).expand();
+            if syms.iter().any(|t| c.has_symbol(t)) {
+                return Err(JsError::new(&format!("system is not linear: {}", eq)));
+            }
+            coeffs.push(c);
+        }
+    }
+    let n = syms.len() as u32;
+    let det = symengine::Matrix::from_vec(n, n, &coeffs)
+        .map_err(|e| JsError::new(&e.to_string()))?
+        .det()
+        .expand();
+    if det.is_zero() {
+        return Err(JsError::new(
+            "system is singular (no unique solution): coefficient determinant is 0",
+        ));
+    }
+    Ok(to_json_list(&symengine::linsolve(&eqs, &syms)))
 }
 
 // ===================== Arithmetic =====================
@@ -198,28 +748,89 @@ wasm_unary!(sym_tan, tan);
 wasm_unary!(sym_asin, asin);
 wasm_unary!(sym_acos, acos);
 wasm_unary!(sym_atan, atan);
+wasm_unary!(sym_csc, csc, csc);
+wasm_unary!(sym_sec, sec, sec);
+wasm_unary!(sym_cot, cot, cot);
+wasm_binary!(sym_atan2, atan2);
 
 // ===================== Hyperbolic =====================
 
 wasm_unary!(sym_sinh, sinh);
 wasm_unary!(sym_cosh, cosh);
 wasm_unary!(sym_tanh, tanh);
+wasm_unary!(sym_asinh, asinh, asinh);
+wasm_unary!(sym_acosh, acosh, acosh);
+wasm_unary!(sym_atanh, atanh, atanh);
 
 // ===================== Exponential / Logarithmic =====================
 
 wasm_unary!(sym_exp, exp);
 wasm_unary!(sym_log, log);
 wasm_unary!(sym_sqrt, sqrt);
+wasm_unary!(sym_cbrt, cbrt);
 
 // ===================== Special functions =====================
 
 wasm_unary!(sym_gamma, gamma);
+wasm_unary!(sym_loggamma, loggamma);
 wasm_unary!(sym_zeta, zeta);
+wasm_unary!(sym_dirichlet_eta, dirichlet_eta);
 wasm_unary!(sym_erf, erf);
+wasm_unary!(sym_erfc, erfc);
 wasm_unary!(sym_lambertw, lambertw);
+wasm_binary!(sym_beta, beta);
+wasm_binary!(sym_lowergamma, lowergamma);
+wasm_binary!(sym_uppergamma, uppergamma);
+wasm_binary!(sym_kronecker_delta, kronecker_delta);
+
+#[wasm_bindgen]
+pub fn sym_polygamma(n: &str, x: &str) -> Result<String, JsError> {
+    let order = parse(n)?;
+    Ok(parse(x)?.polygamma(&order).to_string())
+}
+
+// ===================== Rounding / sign =====================
+
+wasm_unary!(sym_floor, floor);
+wasm_unary!(sym_ceiling, ceiling);
+wasm_unary!(sym_sign, sign);
+
+// ===================== Max / min =====================
+
+/// Symbolic maximum of a comma-separated or JSON list, e.g. sym_max("x, 2, y")
+/// or sym_max('["x", "2", "y"]')
+#[wasm_bindgen]
+pub fn sym_max(exprs_csv: &str) -> Result<String, JsError> {
+    let exprs = parse_list(exprs_csv)?;
+    if exprs.is_empty() {
+        return Err(JsError::new("max() requires at least one expression"));
+    }
+    Ok(symengine::max(&exprs).to_string())
+}
+
+/// Symbolic minimum of a comma-separated or JSON list, e.g. sym_min("x, 2, y")
+/// or sym_min('["x", "2", "y"]')
+#[wasm_bindgen]
+pub fn sym_min(exprs_csv: &str) -> Result<String, JsError> {
+    let exprs = parse_list(exprs_csv)?;
+    if exprs.is_empty() {
+        return Err(JsError::new("min() requires at least one expression"));
+    }
+    Ok(symengine::min(&exprs).to_string())
+}
 
 // ===================== Number theory =====================
 
+/// Exact integer from a decimal string of any length, e.g. "1099511627776"
+/// (2^40), which does not fit the 32-bit integers SymEngine takes directly
+/// on wasm32. Returns SymEngine's canonical form.
+#[wasm_bindgen]
+pub fn integer_from_string(s: &str) -> Result<String, JsError> {
+    symengine::Expr::try_integer_from_str(s.trim())
+        .map(|e| e.to_string())
+        .map_err(|_| JsError::new(&format!("'{}' is not a decimal integer", s)))
+}
+
 #[wasm_bindgen]
 pub fn factorial(n: u32) -> String {
     symengine::factorial(n).to_string()
@@ -231,39 +842,216 @@ pub fn fibonacci(n: u32) -> String {
 }
 
 #[wasm_bindgen]
-pub fn gcd(a: &str, b: &str) -> String {
-    symengine::gcd(&symengine::Expr::parse(a), &symengine::Expr::parse(b)).to_string()
+pub fn lucas(n: u32) -> String {
+    symengine::lucas(n).to_string()
+}
+
+#[wasm_bindgen]
+pub fn gcd(a: &str, b: &str) -> Result<String, JsError> {
+    Ok(symengine::gcd(&parse(a)?, &parse(b)?).to_string())
+}
+
+#[wasm_bindgen]
+pub fn lcm(a: &str, b: &str) -> Result<String, JsError> {
+    Ok(symengine::lcm(&parse(a)?, &parse(b)?).to_string())
+}
+
+#[wasm_bindgen]
+pub fn nextprime(n: &str) -> Result<String, JsError> {
+    Ok(symengine::nextprime(&parse(n)?).to_string())
+}
+
+#[wasm_bindgen]
+pub fn binomial(n: &str, k: u32) -> Result<String, JsError> {
+    Ok(symengine::binomial(&parse(n)?, k).to_string())
+}
+
+/// Parse an integer pair (dividend/divisor or value/modulus), rejecting
+/// non-integers and a zero second argument before they reach SymEngine
+/// (which would abort the instance).
+fn parse_integer_pair(n: &str, d: &str) -> Result<(symengine::Expr, symengine::Expr), JsError> {
+    let n = parse(n)?;
+    let d = parse(d)?;
+    if !n.is_integer() || !d.is_integer() {
+        return Err(JsError::new("both arguments must be integers"));
+    }
+    if d.is_zero() {
+        return Err(JsError::new("division by zero"));
+    }
+    Ok((n, d))
 }
 
 #[wasm_bindgen]
-pub fn lcm(a: &str, b: &str) -> String {
-    symengine::lcm(&symengine::Expr::parse(a), &symengine::Expr::parse(b)).to_string()
+pub fn int_mod(n: &str, d: &str) -> Result<String, JsError> {
+    let (n, d) = parse_integer_pair(n, d)?;
+    Ok(symengine::ntheory_mod(&n, &d).to_string())
 }
 
 #[wasm_bindgen]
-pub fn nextprime(n: &str) -> String {
-    symengine::nextprime(&symengine::Expr::parse(n)).to_string()
+pub fn int_quotient(n: &str, d: &str) -> Result<String, JsError> {
+    let (n, d) = parse_integer_pair(n, d)?;
+    Ok(symengine::ntheory_quotient(&n, &d).to_string())
 }
 
 #[wasm_bindgen]
-pub fn binomial(n: &str, k: u32) -> String {
-    symengine::binomial(&symengine::Expr::parse(n), k).to_string()
+pub fn mod_inverse(a: &str, m: &str) -> Result<String, JsError> {
+    let (a, m) = parse_integer_pair(a, m)?;
+    symengine::mod_inverse(&a, &m)
+        .map(|r| r.to_string())
+        .ok_or_else(|| {
+            JsError::new(&format!("{} has no inverse modulo {}", a, m))
+        })
 }
 
 // ===================== Algebraic =====================
 
 #[wasm_bindgen]
-pub fn numer_denom(expr: &str) -> String {
-    let (n, d) = symengine::Expr::parse(expr).numer_denom();
-    format!("{} | {}", n.to_string(), d.to_string())
+pub fn numer_denom(expr: &str) -> Result<String, JsError> {
+    let (n, d) = parse(expr)?.numer_denom();
+    Ok(format!("{} | {}", n, d))
+}
+
+/// Numerator and denominator as `{"numer": "...", "denom": "..."}`.
+#[wasm_bindgen]
+pub fn numer_denom_json(expr: &str) -> Result<String, JsError> {
+    let (n, d) = parse(expr)?.numer_denom();
+    Ok(serde_json::json!({ "numer": n.to_string(), "denom": d.to_string() }).to_string())
+}
+
+#[wasm_bindgen]
+pub fn numer(expr: &str) -> Result<String, JsError> {
+    Ok(parse(expr)?.numer_denom().0.to_string())
+}
+
+#[wasm_bindgen]
+pub fn denom(expr: &str) -> Result<String, JsError> {
+    Ok(parse(expr)?.numer_denom().1.to_string())
 }
 
 #[wasm_bindgen]
-pub fn coeff(expr: &str, var: &str, n: i32) -> String {
-    let e = symengine::Expr::parse(expr);
-    let x = symengine::Expr::symbol(var);
+pub fn coeff(expr: &str, var: &str, n: i32) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let x = parse_symbol(var)?;
     let ni = symengine::Expr::integer(n);
-    e.coeff(&x, &ni).to_string()
+    Ok(e.coeff(&x, &ni).to_string())
+}
+
+/// Degree of `expr` as a polynomial in `var`, after expanding.
+#[wasm_bindgen]
+pub fn poly_degree(expr: &str, var: &str) -> Result<u32, JsError> {
+    let e = parse(expr)?;
+    let x = parse_symbol(var)?;
+    e.degree(&x)
+        .ok_or_else(|| JsError::new(&format!("'{}' is not a polynomial in {}", e, var)))
+}
+
+/// JSON array of the coefficients of `expr` in `var`, lowest power first.
+#[wasm_bindgen]
+pub fn poly_coeffs(expr: &str, var: &str) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let x = parse_symbol(var)?;
+    let coeffs = e
+        .coeffs(&x)
+        .ok_or_else(|| JsError::new(&format!("'{}' is not a polynomial in {}", e, var)))?;
+    Ok(to_json_list(&coeffs))
+}
+
+// ===================== Comparison =====================
+
+#[wasm_bindgen]
+pub fn expr_equal(a: &str, b: &str) -> Result<bool, JsError> {
+    Ok(parse(a)?.eq(&parse(b)?))
+}
+
+#[wasm_bindgen]
+pub fn expr_not_equal(a: &str, b: &str) -> Result<bool, JsError> {
+    Ok(parse(a)?.neq(&parse(b)?))
+}
+
+/// Structural equality after expanding both sides, so `(x+1)**2` equals
+/// `x**2 + 2*x + 1`.
+#[wasm_bindgen]
+pub fn expr_equal_expanded(a: &str, b: &str) -> Result<bool, JsError> {
+    let a = parse(a)?.expand();
+    let b = parse(b)?.expand();
+    Ok(a.eq(&b))
+}
+
+/// Structural hash as a decimal string (it may exceed 2^53).
+#[wasm_bindgen]
+pub fn expr_hash(expr: &str) -> Result<String, JsError> {
+    Ok(parse(expr)?.hash_value().to_string())
+}
+
+// ===================== Type predicates =====================
+// The numeric predicates (is_zero, is_positive, ...) return false for
+// non-numeric input such as `x + 1`.
+
+wasm_predicate!(is_integer, is_integer);
+wasm_predicate!(is_rational, is_rational);
+wasm_predicate!(is_symbol, is_symbol);
+wasm_predicate!(is_number, is_number);
+wasm_predicate!(is_zero, is_zero);
+wasm_predicate!(is_positive, is_positive);
+wasm_predicate!(is_negative, is_negative);
+wasm_predicate!(is_complex_number, is_complex);
+
+// ===================== Structure =====================
+
+/// SymEngine class name of the outermost node, e.g. "Add", "Mul", "Sin".
+/// Note that `-x` is a Mul.
+#[wasm_bindgen]
+pub fn get_type(expr: &str) -> Result<String, JsError> {
+    Ok(parse(expr)?.type_name())
+}
+
+/// Top-level arguments as a JSON array of strings (`[]` for atoms), e.g.
+/// `get_args("x**2")` → `["x", "2"]`.
+#[wasm_bindgen]
+pub fn get_args(expr: &str) -> Result<String, JsError> {
+    Ok(to_json_list(&parse(expr)?.args()))
+}
+
+/// Number of operation nodes; see `Expr::count_ops`.
+#[wasm_bindgen]
+pub fn count_ops(expr: &str) -> Result<u32, JsError> {
+    Ok(parse(expr)?.count_ops() as u32)
+}
+
+/// Number of top-level arguments (0 for atoms).
+#[wasm_bindgen]
+pub fn num_args(expr: &str) -> Result<u32, JsError> {
+    Ok(parse(expr)?.num_args() as u32)
+}
+
+/// Recursive JSON tree of an expression. Symbols carry `name`, numbers and
+/// other atoms their exact string `value`, other nodes their `args`. Nodes
+/// deeper than `max_depth` are replaced by `{"type": ..., "truncated": true}`.
+fn ast_node(e: &symengine::Expr, depth: u32, max_depth: u32) -> serde_json::Value {
+    use serde_json::json;
+    let ty = e.type_name();
+    if depth > max_depth {
+        return json!({ "type": ty, "truncated": true });
+    }
+    if e.is_symbol() {
+        return json!({ "type": ty, "name": e.to_string() });
+    }
+    let args = e.args();
+    if args.is_empty() {
+        return json!({ "type": ty, "value": e.to_string() });
+    }
+    let children: Vec<serde_json::Value> = args
+        .iter()
+        .map(|a| ast_node(a, depth + 1, max_depth))
+        .collect();
+    json!({ "type": ty, "args": children })
+}
+
+/// Expression tree as JSON, e.g.
+/// `{"type":"Add","args":[{"type":"Integer","value":"2"},{"type":"Symbol","name":"x"}]}`.
+#[wasm_bindgen]
+pub fn to_ast_json(expr: &str, max_depth: u32) -> Result<String, JsError> {
+    Ok(ast_node(&parse(expr)?, 0, max_depth).to_string())
 }
 
 // ===================== String representations =====================
@@ -272,34 +1060,1994 @@ wasm_unary!(to_latex, to_latex);
 wasm_unary!(to_mathml, to_mathml);
 wasm_unary!(to_ccode, to_ccode);
 wasm_unary!(to_jscode, to_jscode);
+wasm_unary!(to_julia, to_julia);
 
-// ===================== Matrix operations =====================
+// ===================== Code generation =====================
 
-/// Determinant. Elements as CSV, row-major. E.g. matrix_det(2, 2, "a, b, c, d")
+/// Parse a JSON array of parameter names for generated code, checking that
+/// they are distinct symbols covering every free symbol of `e`.
+fn parse_params(e: &symengine::Expr, params_json: &str) -> Result<Vec<String>, JsError> {
+    let params = parse_json_list(params_json)?;
+    check_symbols(&params)?;
+    let names: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+    for (i, n) in names.iter().enumerate() {
+        if names[..i].contains(n) {
+            return Err(JsError::new(&format!("duplicate parameter '{}'", n)));
+        }
+    }
+    let missing: Vec<String> = e
+        .free_symbols()
+        .into_iter()
+        .filter(|s| !names.contains(s))
+        .collect();
+    if !missing.is_empty() {
+        return Err(JsError::new(&format!(
+            "free symbols missing from the parameter list: {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(names)
+}
+
+/// Reserved words of strict-mode JavaScript and ES modules, which cannot name
+/// a function or parameter.
+const JS_RESERVED: &[&str] = &[
+    "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default",
+    "delete", "do", "else", "enum", "export", "extends", "false", "finally", "for", "function",
+    "if", "implements", "import", "in", "instanceof", "interface", "let", "new", "null",
+    "package", "private", "protected", "public", "return", "static", "super", "switch", "this",
+    "throw", "true", "try", "typeof", "var", "void", "while", "with", "yield",
+];
+
+/// Whether `name` can be spliced into generated JavaScript as an identifier:
+/// letters, digits, `_` and `$`, not starting with a digit and not reserved.
+fn is_js_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        && !JS_RESERVED.contains(&name)
+}
+
+/// Complete JavaScript source for `expr` as a function of `params_json`,
+/// e.g. `function(x, y) { return Math.sin(x) + Math.pow(y, 2); }`.
 #[wasm_bindgen]
-pub fn matrix_det(rows: u32, cols: u32, elements_csv: &str) -> String {
-    parse_matrix(rows, cols, elements_csv).det().to_string()
+pub fn lambdify_js(expr: &str, params_json: &str) -> Result<String, JsError> {
+    lambdify_js_with(expr, params_json, "function", "")
 }
 
-/// Multiply two matrices (CSV, row-major).
+/// Like `lambdify_js`, with `style` one of `"function"`, `"arrow"` or
+/// `"esm"`; `name` names the function for `"esm"` (`export function name(...)`)
+/// and must be a valid JavaScript identifier, as must every parameter.
 #[wasm_bindgen]
-pub fn matrix_mul(
-    rows_a: u32, cols_a: u32, a_csv: &str,
-    rows_b: u32, cols_b: u32, b_csv: &str,
-) -> String {
-    let ma = parse_matrix(rows_a, cols_a, a_csv);
-    let mb = parse_matrix(rows_b, cols_b, b_csv);
-    ma.mul(&mb).to_string()
+pub fn lambdify_js_with(
+    expr: &str, params_json: &str, style: &str, name: &str,
+) -> Result<String, JsError> {
+    let e = parse(expr)?;
+    let params = parse_params(&e, params_json)?;
+    if let Some(p) = params.iter().find(|p| !is_js_identifier(p)) {
+        return Err(JsError::new(&format!("'{}' is not a valid JavaScript identifier", p)));
+    }
+    let params = params.join(", ");
+    let body = e.to_jscode();
+    match style {
+        "function" => Ok(format!("function({}) {{ return {}; }}", params, body)),
+        "arrow" => Ok(format!("({}) => {}", params, body)),
+        "esm" if name.is_empty() => Err(JsError::new("style \"esm\" requires a function name")),
+        "esm" if !is_js_identifier(name) => Err(JsError::new(&format!(
+            "'{}' is not a valid JavaScript identifier",
+            name
+        ))),
+        "esm" => Ok(format!(
+            "export function {}({}) {{ return {}; }}",
+            name, params, body
+        )),
+        _ => Err(JsError::new(&format!(
+            "unknown style '{}' (expected function, arrow or esm)",
+            style
+        ))),
+    }
 }
 
-/// Invert a square matrix (CSV, row-major).
+/// Complete C function for `expr`, e.g.
+/// `double name(double x, double y) { return ...; }`. Powers are emitted by
+/// `to_ccode` as `pow(...)` calls, never as repeated multiplication.
 #[wasm_bindgen]
-pub fn matrix_inv(rows: u32, cols: u32, elements_csv: &str) -> String {
-    parse_matrix(rows, cols, elements_csv).inv().to_string()
+pub fn generate_c_function(expr: &str, name: &str, params_json: &str) -> Result<String, JsError> {
+    generate_c_function_with(expr, name, params_json, false)
 }
 
-/// Transpose a matrix (CSV, row-major).
+/// Like `generate_c_function`; with `use_float` the signature uses `float`.
+/// The body still calls the double-precision <math.h> functions.
 #[wasm_bindgen]
-pub fn matrix_transpose(rows: u32, cols: u32, elements_csv: &str) -> String {
-    parse_matrix(rows, cols, elements_csv).transpose().to_string()
+pub fn generate_c_function_with(
+    expr: &str, name: &str, params_json: &str, use_float: bool,
+) -> Result<String, JsError> {
+    let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        return Err(JsError::new(&format!("'{}' is not a valid C identifier", name)));
+    }
+    let e = parse(expr)?;
+    let ty = if use_float { "float" } else { "double" };
+    let params: Vec<String> = parse_params(&e, params_json)?
+        .iter()
+        .map(|p| format!("{} {}", ty, p))
+        .collect();
+    let params = if params.is_empty() {
+        "void".to_string()
+    } else {
+        params.join(", ")
+    };
+    Ok(format!(
+        "/* Generated by SymEngine; requires #include <math.h> (link with -lm). */\n\
+         {} {}({}) {{\n    return {};\n}}\n",
+        ty,
+        name,
+        params,
+        e.to_ccode()
+    ))
+}
+
+/// Common subexpression elimination over a JSON array of expressions.
+/// Returns `{"replacements": [["x0", "sin(a)"], ...], "reduced": [...]}`,
+/// with replacements in evaluation order.
+#[wasm_bindgen]
+pub fn cse(exprs_json: &str) -> Result<String, JsError> {
+    let exprs = parse_json_list(exprs_json)?;
+    let (replacements, reduced) = symengine::cse(&exprs);
+    let pairs: Vec<[String; 2]> = replacements
+        .iter()
+        .map(|(s, e)| [s.to_string(), e.to_string()])
+        .collect();
+    let reduced: Vec<String> = reduced.iter().map(|e| e.to_string()).collect();
+    Ok(serde_json::json!({ "replacements": pairs, "reduced": reduced }).to_string())
+}
+
+// ===================== Vector products =====================
+
+/// Dot product of two equal-length vectors given as JSON arrays of strings.
+#[wasm_bindgen]
+pub fn vec_dot(a_json: &str, b_json: &str) -> Result<String, JsError> {
+    let a = parse_json_list(a_json)?;
+    let b = parse_json_list(b_json)?;
+    let d = symengine::dot(&a, &b).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(d.to_string())
+}
+
+/// Cross product of two 3-vectors given as JSON arrays of strings. Returns a
+/// JSON array of the three components.
+#[wasm_bindgen]
+pub fn vec_cross(a_json: &str, b_json: &str) -> Result<String, JsError> {
+    let to_3 = |v: Vec<symengine::Expr>| -> Result<[symengine::Expr; 3], JsError> {
+        let n = v.len();
+        v.try_into().map_err(|_| {
+            JsError::new(&format!("cross product needs 3-component vectors, got {}", n))
+        })
+    };
+    let a = to_3(parse_json_list(a_json)?)?;
+    let b = to_3(parse_json_list(b_json)?)?;
+    Ok(to_json_list(&symengine::cross(&a, &b)))
+}
+
+// ===================== Matrix operations =====================
+
+/// Determinant. Elements as CSV, row-major. E.g. matrix_det(2, 2, "a, b, c, d")
+#[wasm_bindgen]
+pub fn matrix_det(rows: u32, cols: u32, elements_csv: &str) -> Result<String, JsError> {
+    let det = parse_matrix(rows, cols, elements_csv)?
+        .try_det()
+        .map_err(matrix_err("take determinant"))?;
+    Ok(det.to_string())
+}
+
+/// Multiply two matrices (CSV, row-major).
+#[wasm_bindgen]
+pub fn matrix_mul(
+    rows_a: u32, cols_a: u32, a_csv: &str,
+    rows_b: u32, cols_b: u32, b_csv: &str,
+) -> Result<String, JsError> {
+    let ma = parse_matrix(rows_a, cols_a, a_csv)?;
+    let mb = parse_matrix(rows_b, cols_b, b_csv)?;
+    Ok(ma.try_mul(&mb).map_err(matrix_err("multiply matrices"))?.to_raw_string())
+}
+
+/// Jacobian of a list of functions with respect to a list of symbols, both as
+/// JSON arrays of strings. Returns JSON nested arrays, one row per function.
+#[wasm_bindgen]
+pub fn jacobian(funcs_json: &str, vars_json: &str) -> Result<String, JsError> {
+    let funcs = parse_json_list(funcs_json)?;
+    let vars = parse_json_list(vars_json)?;
+    if funcs.is_empty() || vars.is_empty() {
+        return Err(JsError::new("jacobian requires at least one function and one variable"));
+    }
+    check_symbols(&vars)?;
+    let f = symengine::Matrix::from_vec(funcs.len() as u32, 1, &funcs)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    let x = symengine::Matrix::from_vec(vars.len() as u32, 1, &vars)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(matrix_to_json(&f.jacobian(&x)))
+}
+
+/// Invert a square matrix (CSV, row-major).
+#[wasm_bindgen]
+pub fn matrix_inv(rows: u32, cols: u32, elements_csv: &str) -> Result<String, JsError> {
+    let inv = parse_matrix(rows, cols, elements_csv)?
+        .try_inv()
+        .map_err(matrix_err("invert matrix"))?;
+    Ok(inv.to_raw_string())
+}
+
+/// Transpose a matrix (CSV, row-major).
+#[wasm_bindgen]
+pub fn matrix_transpose(rows: u32, cols: u32, elements_csv: &str) -> Result<String, JsError> {
+    let t = parse_matrix(rows, cols, elements_csv)?
+        .try_transpose()
+        .map_err(matrix_err("transpose matrix"))?;
+    Ok(t.to_raw_string())
+}
+
+/// Determinant of a matrix given as JSON nested arrays, e.g.
+/// matrix_det_json('[["a","b"],["c","d"]]').
+#[wasm_bindgen]
+pub fn matrix_det_json(matrix_json: &str) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    Ok(m.try_det().map_err(matrix_err("take determinant"))?.to_string())
+}
+
+/// Multiply two matrices given as JSON nested arrays. Returns JSON nested
+/// arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_mul_json(a_json: &str, b_json: &str) -> Result<String, JsError> {
+    let a = parse_json_matrix(a_json)?;
+    let b = parse_json_matrix(b_json)?;
+    Ok(matrix_to_json(&a.try_mul(&b).map_err(matrix_err("multiply matrices"))?))
+}
+
+/// Invert a square matrix given as JSON nested arrays. Returns JSON nested
+/// arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_inv_json(matrix_json: &str) -> Result<String, JsError> {
+    let inv = parse_json_matrix(matrix_json)?
+        .try_inv()
+        .map_err(matrix_err("invert matrix"))?;
+    Ok(matrix_to_json(&inv))
+}
+
+/// Transpose a matrix given as JSON nested arrays. Returns JSON nested
+/// arrays, row-major, so `[["a","b","c"]]` becomes `[["a"],["b"],["c"]]`.
+#[wasm_bindgen]
+pub fn matrix_transpose_json(matrix_json: &str) -> Result<String, JsError> {
+    let t = parse_json_matrix(matrix_json)?
+        .try_transpose()
+        .map_err(matrix_err("transpose matrix"))?;
+    Ok(matrix_to_json(&t))
+}
+
+/// Add two matrices of the same shape, both given as JSON nested arrays.
+/// Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_add(a_json: &str, b_json: &str) -> Result<String, JsError> {
+    let a = parse_json_matrix(a_json)?;
+    let b = parse_json_matrix(b_json)?;
+    Ok(matrix_to_json(&a.try_add(&b).map_err(matrix_err("add matrices"))?))
+}
+
+/// Subtract matrix B from matrix A, both given as JSON nested arrays of the
+/// same shape. Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_sub(a_json: &str, b_json: &str) -> Result<String, JsError> {
+    let a = parse_json_matrix(a_json)?;
+    let b = parse_json_matrix(b_json)?;
+    Ok(matrix_to_json(&a.try_sub(&b).map_err(matrix_err("subtract matrices"))?))
+}
+
+/// Multiply every entry of a matrix (JSON nested arrays) by a scalar
+/// expression, which may be symbolic, e.g. matrix_scale(m, "1/det").
+/// The scalar is parsed once. Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_scale(matrix_json: &str, scalar: &str) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let s = parse(scalar)?;
+    Ok(matrix_to_json(&m.mul_scalar(&s)))
+}
+
+/// Rank of a matrix given as JSON nested arrays. Symbolic entries are zero
+/// only if they expand to 0; see `Matrix::rank`.
+#[wasm_bindgen]
+pub fn matrix_rank(matrix_json: &str) -> Result<u32, JsError> {
+    Ok(parse_json_matrix(matrix_json)?.rank())
+}
+
+/// Solve A·x = b, both given as JSON nested arrays. b is n×1, or n×k for k
+/// right-hand sides at once. Returns x as JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_solve(a_json: &str, b_json: &str) -> Result<String, JsError> {
+    let a = parse_json_matrix(a_json)?;
+    let b = parse_json_matrix(b_json)?;
+    let x = a.solve(&b).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(matrix_to_json(&x))
+}
+
+/// Eigenvalues of a square matrix given as JSON nested arrays, as a JSON
+/// array of expression strings. Repeated eigenvalues are listed once.
+#[wasm_bindgen]
+pub fn matrix_eigenvalues(matrix_json: &str) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let eigs = m.eigenvalues().map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(to_json_list(&eigs))
+}
+
+/// Reduced row echelon form of a matrix given as JSON nested arrays.
+/// Symbolic pivots are assumed nonzero. Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_rref(matrix_json: &str) -> Result<String, JsError> {
+    Ok(matrix_to_json(&parse_json_matrix(matrix_json)?.rref()))
+}
+
+/// LU factorization of a square matrix given as JSON nested arrays. Returns
+/// `{"L": [[...]], "U": [[...]]}` with L unit lower triangular. No row swaps
+/// are made, so a zero leading principal minor is an error.
+#[wasm_bindgen]
+pub fn matrix_lu(matrix_json: &str) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let (l, u) = m.lu().map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(serde_json::json!({ "L": matrix_rows(&l), "U": matrix_rows(&u) }).to_string())
+}
+
+/// LDL factorization of a symmetric matrix given as JSON nested arrays.
+/// Returns `{"L": [[...]], "D": [[...]]}` with A = L·D·Lᵀ.
+#[wasm_bindgen]
+pub fn matrix_ldl(matrix_json: &str) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let (l, d) = m.ldl().map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(serde_json::json!({ "L": matrix_rows(&l), "D": matrix_rows(&d) }).to_string())
+}
+
+/// Fraction-free LU factorization of a square matrix given as JSON nested
+/// arrays. Returns `{"LU": [[...]]}`, L (below the diagonal) and U packed
+/// into one matrix.
+#[wasm_bindgen]
+pub fn matrix_fflu(matrix_json: &str) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let lu = m.fflu().map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(serde_json::json!({ "LU": matrix_rows(&lu) }).to_string())
+}
+
+/// Fraction-free LDU factorization. Returns `{"L": .., "D": .., "U": ..}`.
+#[wasm_bindgen]
+pub fn matrix_ffldu(matrix_json: &str) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let (l, d, u) = m.ffldu().map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(serde_json::json!({
+        "L": matrix_rows(&l),
+        "D": matrix_rows(&d),
+        "U": matrix_rows(&u),
+    })
+    .to_string())
+}
+
+/// n×n identity matrix as JSON nested arrays.
+#[wasm_bindgen]
+pub fn matrix_identity(n: u32) -> Result<String, JsError> {
+    check_shape(n, n)?;
+    Ok(matrix_to_json(&symengine::Matrix::identity(n)))
+}
+
+/// rows × cols zero matrix as JSON nested arrays.
+#[wasm_bindgen]
+pub fn matrix_zeros(rows: u32, cols: u32) -> Result<String, JsError> {
+    check_shape(rows, cols)?;
+    Ok(matrix_to_json(&symengine::Matrix::zeros(rows, cols)))
+}
+
+/// rows × cols matrix of ones as JSON nested arrays.
+#[wasm_bindgen]
+pub fn matrix_ones(rows: u32, cols: u32) -> Result<String, JsError> {
+    check_shape(rows, cols)?;
+    Ok(matrix_to_json(&symengine::Matrix::ones(rows, cols)))
+}
+
+/// Diagonal matrix from a JSON array of entries, e.g. matrix_diag('["a","b"]').
+#[wasm_bindgen]
+pub fn matrix_diag(entries_json: &str) -> Result<String, JsError> {
+    let entries = parse_json_list(entries_json)?;
+    check_shape(entries.len() as u32, entries.len() as u32)?;
+    Ok(matrix_to_json(&symengine::Matrix::diag(&entries)))
+}
+
+/// Join two matrices side by side, `[A | B]`. Both are JSON nested arrays
+/// with the same number of rows. Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_hstack(a_json: &str, b_json: &str) -> Result<String, JsError> {
+    let a = parse_json_matrix(a_json)?;
+    let b = parse_json_matrix(b_json)?;
+    let m = a.hstack(&b).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(matrix_to_json(&m))
+}
+
+/// Stack matrix B below matrix A. Both are JSON nested arrays with the same
+/// number of columns. Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_vstack(a_json: &str, b_json: &str) -> Result<String, JsError> {
+    let a = parse_json_matrix(a_json)?;
+    let b = parse_json_matrix(b_json)?;
+    let m = a.vstack(&b).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(matrix_to_json(&m))
+}
+
+/// Delete row i (0-based) of a matrix given as JSON nested arrays. Deleting
+/// the only row gives `[]`. Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_delete_row(matrix_json: &str, i: u32) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let m = m.delete_row(i).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(matrix_to_json(&m))
+}
+
+/// Delete column j (0-based) of a matrix given as JSON nested arrays.
+/// Deleting the only column leaves one empty array per row.
+#[wasm_bindgen]
+pub fn matrix_delete_col(matrix_json: &str, j: u32) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let m = m.delete_col(j).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(matrix_to_json(&m))
+}
+
+/// Entry at row r, column c (0-based) of a matrix given as JSON nested arrays.
+#[wasm_bindgen]
+pub fn matrix_get(matrix_json: &str, r: u32, c: u32) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    check_position(&m, r, c)?;
+    Ok(m.get(r, c).to_string())
+}
+
+/// Replace the entry at row r, column c (0-based) with the expression
+/// `value`. Returns the updated matrix as JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_set(matrix_json: &str, r: u32, c: u32, value: &str) -> Result<String, JsError> {
+    let mut m = parse_json_matrix(matrix_json)?;
+    check_position(&m, r, c)?;
+    m.set(r, c, &parse(value)?);
+    Ok(matrix_to_json(&m))
+}
+
+/// Whether two matrices (JSON nested arrays) have the same shape and
+/// structurally equal entries. A shape mismatch is `false`, not an error.
+#[wasm_bindgen]
+pub fn matrix_equal(a_json: &str, b_json: &str) -> Result<bool, JsError> {
+    Ok(parse_json_matrix(a_json)?.eq(&parse_json_matrix(b_json)?))
+}
+
+/// Like `matrix_equal`, but entries are expanded first, so `(x+1)**2`
+/// equals `x**2 + 2*x + 1`.
+#[wasm_bindgen]
+pub fn matrix_equal_expanded(a_json: &str, b_json: &str) -> Result<bool, JsError> {
+    Ok(parse_json_matrix(a_json)?.eq_expanded(&parse_json_matrix(b_json)?))
+}
+
+/// n-th power of a square matrix given as JSON nested arrays; n = 0 gives
+/// the identity. Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_pow(matrix_json: &str, n: u32) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let p = m.pow(n).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(matrix_to_json(&p))
+}
+
+/// Kronecker product of two matrices given as JSON nested arrays. Returns
+/// JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_kron(a_json: &str, b_json: &str) -> Result<String, JsError> {
+    let a = parse_json_matrix(a_json)?;
+    let b = parse_json_matrix(b_json)?;
+    Ok(matrix_to_json(&a.kron(&b)))
+}
+
+/// Differentiate every entry of a matrix (JSON nested arrays) with respect
+/// to the symbol `var`. Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_diff(matrix_json: &str, var: &str) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let v = parse(var)?;
+    check_symbols(std::slice::from_ref(&v))?;
+    Ok(matrix_to_json(&m.diff(&v)))
+}
+
+/// Apply a named operation to every entry of a matrix given as JSON nested
+/// arrays, e.g. matrix_apply(m, "expand"). See `MATRIX_OPS` for the names.
+/// Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_apply(matrix_json: &str, op: &str) -> Result<String, JsError> {
+    let f = matrix_op(op)?;
+    Ok(matrix_to_json(&parse_json_matrix(matrix_json)?.map(f)))
+}
+
+/// Frobenius norm of a matrix given as JSON nested arrays.
+#[wasm_bindgen]
+pub fn matrix_frobenius_norm(matrix_json: &str) -> Result<String, JsError> {
+    Ok(parse_json_matrix(matrix_json)?.frobenius_norm().to_string())
+}
+
+/// Squared Frobenius norm, which avoids the square root and so stays
+/// rational for rational entries.
+#[wasm_bindgen]
+pub fn matrix_frobenius_norm_squared(matrix_json: &str) -> Result<String, JsError> {
+    Ok(parse_json_matrix(matrix_json)?.frobenius_norm_squared().to_string())
+}
+
+/// Simultaneous substitution into every entry of a matrix (JSON nested
+/// arrays) from a JSON object of replacements, as in `substitute_many`.
+/// Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn matrix_substitute(matrix_json: &str, map_json: &str) -> Result<String, JsError> {
+    let m = parse_json_matrix(matrix_json)?;
+    let pairs = parse_subs_map(map_json)?;
+    let refs: Vec<(&symengine::Expr, &symengine::Expr)> =
+        pairs.iter().map(|(k, v)| (k, v)).collect();
+    Ok(matrix_to_json(&m.subs_map(&refs)))
+}
+
+// ===================== Sparse matrices =====================
+
+/// Parse `[[row, col, "value"], ...]` into a sparse matrix of the given
+/// shape. Values at the same position are added together.
+fn parse_sparse(
+    triplets_json: &str,
+    rows: u32,
+    cols: u32,
+) -> Result<symengine::SparseMatrix, JsError> {
+    let items: Vec<(u32, u32, String)> = serde_json::from_str(triplets_json).map_err(|e| {
+        JsError::new(&format!("expected a JSON array of [row, col, value] triplets: {}", e))
+    })?;
+    let triplets = items
+        .iter()
+        .map(|(r, c, s)| Ok((*r, *c, parse(s)?)))
+        .collect::<Result<Vec<_>, JsError>>()?;
+    symengine::SparseMatrix::from_triplets(rows, cols, &triplets)
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Normalise triplets through SymEngine's sparse (CSR) format: duplicates
+/// are summed, zeros dropped and the result sorted row-major. Returns the
+/// nonzero entries as JSON triplets in the same `[row, col, "value"]` form.
+#[wasm_bindgen]
+pub fn sparse_from_triplets(triplets_json: &str, rows: u32, cols: u32) -> Result<String, JsError> {
+    let m = parse_sparse(triplets_json, rows, cols)?;
+    let out: Vec<(u32, u32, String)> = m
+        .triplets()
+        .into_iter()
+        .map(|(r, c, e)| (r, c, e.to_string()))
+        .collect();
+    Ok(serde_json::to_string(&out).unwrap())
+}
+
+/// Expand triplets (as for `sparse_from_triplets`) into a rows × cols dense
+/// matrix. Returns JSON nested arrays, row-major.
+#[wasm_bindgen]
+pub fn sparse_to_dense(triplets_json: &str, rows: u32, cols: u32) -> Result<String, JsError> {
+    Ok(matrix_to_json(&parse_sparse(triplets_json, rows, cols)?.to_dense()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    /// Compare expression strings after expanding, so term order and
+    /// factoring in the printed form don't matter.
+    #[track_caller]
+    fn assert_expr(actual: &str, expected: &str) {
+        assert!(
+            expr_equal_expanded(actual, expected).unwrap(),
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[track_caller]
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-12 * expected.abs().max(1.0),
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn csc_sec_cot() {
+        assert_eq!(sym_csc("x").unwrap(), "csc(x)");
+        assert_eq!(sym_sec("x").unwrap(), "sec(x)");
+        assert_eq!(sym_cot("x").unwrap(), "cot(x)");
+        let v = evalf(&sym_csc("pi/2").unwrap()).unwrap();
+        assert_close(eval_double(&v).unwrap(), 1.0);
+        assert_expr(&differentiate(&sym_csc("x").unwrap(), "x").unwrap(), "-cot(x)*csc(x)");
+        assert_expr(&differentiate(&sym_sec("x").unwrap(), "x").unwrap(), "tan(x)*sec(x)");
+        assert_expr(&differentiate(&sym_cot("x").unwrap(), "x").unwrap(), "-1 - cot(x)**2");
+    }
+
+    #[wasm_bindgen_test]
+    fn inverse_hyperbolic() {
+        assert_eq!(sym_asinh("x").unwrap(), "asinh(x)");
+        assert_eq!(sym_acosh("x").unwrap(), "acosh(x)");
+        assert_eq!(sym_atanh("x").unwrap(), "atanh(x)");
+        let d = differentiate(&sym_asinh("x").unwrap(), "x").unwrap();
+        assert_expr(&d, "1/sqrt(1 + x**2)");
+        let v = evalf(&sym_atanh("1/2").unwrap()).unwrap();
+        assert_close(eval_double(&v).unwrap(), 0.5493061443340549);
+        // Out-of-domain arguments come back as values, not traps.
+        assert!(sym_acosh("0").is_ok());
+        assert!(sym_atanh("1").is_ok());
+        assert_eq!(expand("x + x").unwrap(), "2*x");
+    }
+
+    #[wasm_bindgen_test]
+    fn cbrt() {
+        assert_expr(&sym_cbrt("27").unwrap(), "3");
+        assert_expr(&sym_cbrt("x**3").unwrap(), "(x**3)**(1/3)");
+        assert!(sym_cbrt("-8").is_ok());
+        let v = evalf(&sym_cbrt("2").unwrap()).unwrap();
+        assert_close(eval_double(&v).unwrap(), 2f64.cbrt());
+        let c = sym_cbrt("x").unwrap();
+        assert_expr(&expand(&format!("(1 + {})**2", c)).unwrap(), "1 + 2*x**(1/3) + x**(2/3)");
+    }
+
+    #[wasm_bindgen_test]
+    fn floor_ceiling_sign() {
+        assert_eq!(sym_floor("7/2").unwrap(), "3");
+        assert_eq!(sym_ceiling("7/2").unwrap(), "4");
+        assert_eq!(sym_floor("-7/2").unwrap(), "-4");
+        assert_eq!(sym_ceiling("-7/2").unwrap(), "-3");
+        assert_eq!(sym_sign("-7/2").unwrap(), "-1");
+        assert_eq!(sym_sign("0").unwrap(), "0");
+        assert_eq!(sym_floor("x").unwrap(), "floor(x)");
+        assert_eq!(sym_ceiling("x").unwrap(), "ceiling(x)");
+        assert_eq!(sym_sign("x").unwrap(), "sign(x)");
+    }
+
+    #[wasm_bindgen_test]
+    fn erfc() {
+        let v = evalf(&sym_erfc("3").unwrap()).unwrap();
+        assert_close(eval_double(&v).unwrap(), 2.209049699858544e-5);
+        let d = differentiate(&sym_erfc("x").unwrap(), "x").unwrap();
+        assert_expr(&d, "-2*exp(-x**2)/sqrt(pi)");
+    }
+
+    #[wasm_bindgen_test]
+    fn loggamma() {
+        assert_eq!(sym_loggamma("x").unwrap(), "loggamma(x)");
+        let v = evalf(&sym_loggamma("100").unwrap()).unwrap();
+        assert_close(eval_double(&v).unwrap(), 359.1342053695754);
+    }
+
+    #[wasm_bindgen_test]
+    fn dirichlet_eta() {
+        let v = evalf(&sym_dirichlet_eta("1").unwrap()).unwrap();
+        assert_close(eval_double(&v).unwrap(), std::f64::consts::LN_2);
+        assert_eq!(sym_dirichlet_eta("s").unwrap(), "dirichlet_eta(s)");
+    }
+
+    #[wasm_bindgen_test]
+    fn beta() {
+        assert_expr(&sym_beta("2", "3").unwrap(), "1/12");
+        let b = sym_beta("a", "3").unwrap();
+        assert_eq!(get_type(&b).unwrap(), "Beta");
+        // d/da B(a, 3) = B(a, 3)·(ψ(a) − ψ(a + 3)), which is −13/144 at a = 2.
+        let da = differentiate(&b, "a").unwrap();
+        assert_expr(&substitute(&da, "a", "2").unwrap(), "-13/144");
+        let db = differentiate(&sym_beta("3", "b").unwrap(), "b").unwrap();
+        assert_expr(&substitute(&db, "b", "2").unwrap(), "-13/144");
+    }
+
+    #[wasm_bindgen_test]
+    fn polygamma() {
+        assert_eq!(sym_polygamma("1", "x").unwrap(), "polygamma(1, x)");
+        let d = differentiate(&sym_polygamma("0", "x").unwrap(), "x").unwrap();
+        assert_eq!(d, "polygamma(1, x)");
+        let euler = 0.5772156649015329;
+        for (x, expected) in [("1", -euler), ("2", 1.0 - euler), ("3", 1.5 - euler)] {
+            let v = evalf(&sym_polygamma("0", x).unwrap()).unwrap();
+            assert_close(eval_double(&v).unwrap(), expected);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn atan2() {
+        assert_expr(&sym_atan2("1", "-1").unwrap(), "3*pi/4");
+        let v = evalf(&sym_atan2("1", "-1").unwrap()).unwrap();
+        assert_close(eval_double(&v).unwrap(), 3.0 * std::f64::consts::FRAC_PI_4);
+        let e = sym_atan2("y", "x").unwrap();
+        let at = r#"{"x": "1", "y": "1"}"#;
+        let dy = differentiate(&e, "y").unwrap();
+        assert_expr(&substitute_many(&dy, at).unwrap(), "1/2");
+        let dx = differentiate(&e, "x").unwrap();
+        assert_expr(&substitute_many(&dx, at).unwrap(), "-1/2");
+    }
+
+    #[wasm_bindgen_test]
+    fn incomplete_gamma() {
+        // Integer orders reduce to elementary functions.
+        assert_expr(&sym_lowergamma("1", "x").unwrap(), "1 - exp(-x)");
+        assert_expr(&sym_uppergamma("1", "x").unwrap(), "exp(-x)");
+        let lower = sym_lowergamma("s", "x").unwrap();
+        let upper = sym_uppergamma("s", "x").unwrap();
+        assert_expr(&differentiate(&lower, "x").unwrap(), "x**(s - 1)*exp(-x)");
+        assert_expr(&differentiate(&upper, "x").unwrap(), "-x**(s - 1)*exp(-x)");
+        assert!(to_latex(&lower).unwrap().contains("gamma"));
+        let v = sym_lowergamma("2", "1").unwrap();
+        assert_close(eval_double(&evalf(&v).unwrap()).unwrap(), 1.0 - 2.0 / std::f64::consts::E);
+        let sum = add(&sym_lowergamma("3", "2").unwrap(), &sym_uppergamma("3", "2").unwrap()).unwrap();
+        assert_close(eval_double(&sum).unwrap(), 2.0);
+        assert!(sym_lowergamma("s", "(").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn kronecker_delta() {
+        assert_eq!(sym_kronecker_delta("2", "2").unwrap(), "1");
+        assert_eq!(sym_kronecker_delta("2", "3").unwrap(), "0");
+        assert_eq!(sym_kronecker_delta("i", "i").unwrap(), "1");
+        for (i, j) in [("i", "j"), ("i", "2"), ("2", "j")] {
+            let d = sym_kronecker_delta(i, j).unwrap();
+            assert_eq!(get_type(&d).unwrap(), "KroneckerDelta");
+        }
+    }
+
+    /// Message of the JS error returned by a failed export.
+    #[track_caller]
+    fn err_msg<T>(r: Result<T, JsError>) -> String {
+        match r {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => js_sys::Error::from(JsValue::from(e)).message().into(),
+        }
+    }
+
+    /// The module must stay usable after an export rejects its input.
+    #[track_caller]
+    fn assert_still_works() {
+        assert_expr(&expand("(x + 1)**2").unwrap(), "x**2 + 2*x + 1");
+    }
+
+    #[wasm_bindgen_test]
+    fn symbolic_max_and_min() {
+        assert_eq!(sym_max("1, 5, -3").unwrap(), "5");
+        assert_eq!(sym_min("1, 5, -3").unwrap(), "-3");
+        assert_eq!(sym_max("x").unwrap(), "x");
+        assert_eq!(sym_min("2/3").unwrap(), "2/3");
+        // Numeric arguments are reduced to one; the symbol stays.
+        let m = sym_max("x, 2, 7").unwrap();
+        assert!(m.contains('x') && m.contains('7') && !m.contains('2'), "{}", m);
+        assert_eq!(substitute(&m, "x", "10").unwrap(), "10");
+        assert_eq!(substitute(&m, "x", "0").unwrap(), "7");
+        let m = sym_min("x, 2, 7").unwrap();
+        assert_eq!(substitute(&m, "x", "-1").unwrap(), "-1");
+        assert_eq!(err_msg(sym_max("")), "max() requires at least one expression");
+        assert_eq!(err_msg(sym_min("[]")), "min() requires at least one expression");
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn max_and_min_lists() {
+        assert_eq!(sym_max(r#"["1", "5", "-3"]"#).unwrap(), "5");
+        assert_eq!(sym_min(r#" ["x"]"#).unwrap(), "x");
+        assert_eq!(sym_max(r#"["atan2(1, 1)", "0"]"#).unwrap(), sym_max("atan2(1, 1), 0").unwrap());
+        // Commas inside calls do not split entries.
+        let nested = sym_max("max(a, b), c").unwrap();
+        assert_eq!(substitute(&substitute(&nested, "a", "1").unwrap(), "b", "4").unwrap(), sym_max("4, c").unwrap());
+        assert_expr(&sym_min("beta(s, t)").unwrap(), "beta(s, t)");
+        assert_eq!(err_msg(sym_max("x,,y")), "entry 2 of the list is empty");
+        assert_eq!(err_msg(sym_min("x, y,")), "entry 3 of the list is empty");
+        assert_eq!(err_msg(sym_min(" , ")), "entry 1 of the list is empty");
+        assert!(err_msg(sym_max(r#"["x", 2]"#)).starts_with("expected a JSON array of strings"));
+        assert_eq!(matrix_det(2, 2, "atan2(y, x), 0, 0, 2").unwrap(), parse_expr("2*atan2(y, x)", true).unwrap());
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn lucas_numbers() {
+        assert_eq!(lucas(0), "2");
+        assert_eq!(lucas(1), "1");
+        for n in [2, 10, 93, 300] {
+            let sum = add(&fibonacci(n - 1), &fibonacci(n + 1)).unwrap();
+            assert_eq!(lucas(n), sum, "lucas({})", n);
+        }
+        assert!(lucas(300).len() > 20);
+    }
+
+    #[wasm_bindgen_test]
+    fn integer_mod_and_quotient() {
+        assert_eq!(int_mod("17", "5").unwrap(), "2");
+        assert_eq!(int_quotient("17", "5").unwrap(), "3");
+        // SymEngine truncates towards zero, so the remainder takes the
+        // dividend's sign.
+        assert_eq!(int_mod("-7", "3").unwrap(), "-1");
+        assert_eq!(int_quotient("-7", "3").unwrap(), "-2");
+        assert_eq!(err_msg(int_mod("7", "0")), "division by zero");
+        assert_eq!(err_msg(int_quotient("7", "0")), "division by zero");
+        assert_eq!(err_msg(int_mod("7/2", "3")), "both arguments must be integers");
+        assert_eq!(int_mod("10", "4").unwrap(), "2");
+    }
+
+    #[wasm_bindgen_test]
+    fn modular_inverse() {
+        assert_eq!(mod_inverse("3", "7").unwrap(), "5");
+        assert_eq!(err_msg(mod_inverse("2", "4")), "2 has no inverse modulo 4");
+        assert_eq!(mod_inverse("3", "1000000007").unwrap(), "333333336");
+        // 2^61 - 1 is prime and does not fit in 32 bits.
+        assert_eq!(
+            mod_inverse("2", "2305843009213693951").unwrap(),
+            "1152921504606846976"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn julia_code() {
+        let code = to_julia("x**2 + sin(x)").unwrap();
+        assert!(code.contains("x^2"), "{}", code);
+        assert!(code.contains("sin(x)"), "{}", code);
+        assert!(!code.contains("**"), "{}", code);
+        // Rationals stay exact rather than becoming a truncated decimal.
+        let third = to_julia("1/3").unwrap();
+        assert!(third.contains('3') && !third.contains('.'), "{}", third);
+    }
+
+    #[wasm_bindgen_test]
+    fn named_constants() {
+        let expected = [
+            ("pi", "pi"),
+            ("e", "E"),
+            ("euler_gamma", "EulerGamma"),
+            ("catalan", "Catalan"),
+            ("golden_ratio", "GoldenRatio"),
+            ("i", "I"),
+            ("oo", "oo"),
+            ("-oo", "-oo"),
+            ("zoo", "zoo"),
+            ("nan", "nan"),
+        ];
+        for (name, printed) in expected {
+            assert_eq!(constant(name).unwrap(), printed, "{}", name);
+        }
+        let value = |name| eval_double(&constant_evalf(name, 53).unwrap()).unwrap();
+        assert_close(value("pi"), std::f64::consts::PI);
+        assert_close(value("e"), std::f64::consts::E);
+        assert_close(value("golden_ratio"), (1.0 + 5f64.sqrt()) / 2.0);
+        assert_close(value("euler_gamma"), 0.5772156649015329);
+        assert_close(value("catalan"), 0.915965594177219);
+        assert_eq!(
+            err_msg(constant("tau")),
+            format!("unknown constant 'tau' (expected one of: {})", CONSTANT_NAMES)
+        );
+        assert!(constant_evalf("tau", 53).is_err());
+        assert!(constant_evalf("pi", 128).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn evalf_with_precision() {
+        let r = evalf_prec("sqrt(2)", 53, true).unwrap();
+        assert_close(eval_double(&r).unwrap(), std::f64::consts::SQRT_2);
+        let i = evalf_prec("sqrt(-1)", 53, false).unwrap();
+        assert_eq!(eval_complex(&i).unwrap(), vec![0.0, 1.0]);
+        assert!(err_msg(evalf_prec("sqrt(2)", 64, true)).contains("unsupported precision of 64 bits"));
+        assert!(err_msg(evalf_prec("sqrt(2)", 0, true)).contains("unsupported precision"));
+    }
+
+    #[wasm_bindgen_test]
+    fn eval_to_double() {
+        assert_eq!(eval_double("3").unwrap(), 3.0);
+        assert_eq!(eval_double("-1/4").unwrap(), -0.25);
+        assert_close(eval_double("2*pi").unwrap(), std::f64::consts::TAU);
+        assert_eq!(
+            err_msg(eval_double("sqrt(-1)")),
+            "expression does not evaluate to a real number"
+        );
+        assert!(err_msg(eval_double("x + 1")).contains("free symbols x"));
+    }
+
+    #[wasm_bindgen_test]
+    fn eval_to_complex() {
+        assert_eq!(eval_complex("2").unwrap(), vec![2.0, 0.0]);
+        let z = eval_complex("sqrt(-2)").unwrap();
+        assert_close(z[0], 0.0);
+        assert_close(z[1], std::f64::consts::SQRT_2);
+        let w = eval_complex(&evalf("exp(I*pi/3)").unwrap()).unwrap();
+        assert_close(w[0], 0.5);
+        assert_close(w[1], 3f64.sqrt() / 2.0);
+        assert!(err_msg(eval_complex("x*I")).contains("free symbols x"));
+    }
+
+    #[wasm_bindgen_test]
+    fn linear_systems() {
+        let eqs = r#"["x + y + z - 6", "a*x - y", "y - z"]"#;
+        let sol: Vec<String> = serde_json::from_str(&linsolve(eqs, r#"["x", "y", "z"]"#).unwrap()).unwrap();
+        assert_eq!(sol.len(), 3);
+        // x = 6/(1 + 2a), y = z = 6a/(1 + 2a)
+        for (s, expected) in sol.iter().zip(["6/7", "18/7", "18/7"]) {
+            assert_expr(&substitute(s, "a", "3").unwrap(), expected);
+        }
+        assert!(err_msg(linsolve(r#"["x + y - 1", "2*x + 2*y - 2"]"#, r#"["x", "y"]"#))
+            .starts_with("system is singular"));
+        assert!(err_msg(linsolve(r#"["x + y - 1", "x + y - 2"]"#, r#"["x", "y"]"#))
+            .starts_with("system is singular"));
+        assert!(err_msg(linsolve(r#"["x*y - 1", "x - y"]"#, r#"["x", "y"]"#))
+            .starts_with("system is not linear"));
+        assert!(err_msg(linsolve(r#"["x - 1"]"#, r#"["x", "y"]"#))
+            .starts_with("expected as many equations as symbols"));
+    }
+
+    #[wasm_bindgen_test]
+    fn solve_poly_as_json() {
+        let roots = |e: &str| -> Vec<String> {
+            serde_json::from_str(&solve_poly_json(e, "x").unwrap()).unwrap()
+        };
+        let mut quadratic = roots("x**2 - 5*x + 6");
+        quadratic.sort();
+        assert_eq!(quadratic, ["2", "3"]);
+        let quartic = roots("x**4 - 1");
+        assert_eq!(quartic.len(), 4);
+        let mut complex = 0;
+        for r in &quartic {
+            let z = eval_complex(&format!("({})**4 - 1", r)).unwrap();
+            assert!(z[0].abs() < 1e-12 && z[1].abs() < 1e-12, "{} is not a root", r);
+            if eval_complex(r).unwrap()[1].abs() > 1e-12 {
+                complex += 1;
+            }
+        }
+        assert_eq!(complex, 2);
+        assert!(roots("1").is_empty());
+        assert_eq!(solve_poly_json("1", "x").unwrap(), "[]");
+    }
+
+    #[wasm_bindgen_test]
+    fn numerator_and_denominator() {
+        let parts = |e: &str| -> (String, String) {
+            let v: serde_json::Value = serde_json::from_str(&numer_denom_json(e).unwrap()).unwrap();
+            (v["numer"].as_str().unwrap().into(), v["denom"].as_str().unwrap().into())
+        };
+        let (n, d) = parts("(x + 1)/(x - 1)");
+        assert_expr(&n, "x + 1");
+        assert_expr(&d, "x - 1");
+        let (n, d) = parts("x**2 + 1");
+        assert_expr(&n, "x**2 + 1");
+        assert_eq!(d, "1");
+        // 1/(1 + 1/x) is normalized to x/(x + 1).
+        let (n, d) = parts("1/(1 + 1/x)");
+        assert_expr(&n, "x");
+        assert_expr(&d, "x + 1");
+        assert_expr(&numer("y/(x + 1)").unwrap(), "y");
+        assert_expr(&denom("y/(x + 1)").unwrap(), "x + 1");
+        assert_eq!(denom("x**2 + 1").unwrap(), "1");
+    }
+
+    #[wasm_bindgen_test]
+    fn simultaneous_substitution() {
+        let swapped = substitute_many("x + 2*y", r#"{"x": "y", "y": "x"}"#).unwrap();
+        assert_expr(&swapped, "y + 2*x");
+        // Sequential substitution collapses both symbols into one.
+        let sequential = substitute(&substitute("x + 2*y", "x", "y").unwrap(), "y", "x").unwrap();
+        assert_expr(&sequential, "3*x");
+        assert_expr(&substitute_many("x*y", r#"{"x": "2", "y": "z + 1"}"#).unwrap(), "2*z + 2");
+        assert_eq!(substitute_many("x", "{}").unwrap(), "x");
+        assert!(err_msg(substitute_many("x", r#"["x"]"#)).starts_with("expected a JSON object"));
+    }
+
+    #[wasm_bindgen_test]
+    fn higher_order_derivatives() {
+        let e = "sin(x)*exp(x)";
+        // d⁵/dx⁵ eˣ·sin x = 2^(5/2)·eˣ·sin(x + 5π/4) = −4eˣ(sin x + cos x)
+        let d5 = differentiate_n(e, "x", 5).unwrap();
+        assert_expr(&d5, "-4*exp(x)*(sin(x) + cos(x))");
+        let mut repeated = e.to_string();
+        for _ in 0..5 {
+            repeated = differentiate(&repeated, "x").unwrap();
+        }
+        assert!(expr_equal(&d5, &repeated).unwrap());
+        assert!(expr_equal(&differentiate_n(e, "x", 0).unwrap(), e).unwrap());
+        assert_eq!(differentiate_n("x**3", "x", 4).unwrap(), "0");
+    }
+
+    fn json_list(json: &str) -> Vec<String> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn gradients() {
+        let g = json_list(&gradient("x*y + sin(x*z)", r#"["x", "y", "z"]"#).unwrap());
+        assert_eq!(g.len(), 3);
+        assert_expr(&g[0], "y + z*cos(x*z)");
+        assert_expr(&g[1], "x");
+        assert_expr(&g[2], "x*cos(x*z)");
+        // A variable the expression doesn't contain has a zero partial.
+        let g = json_list(&gradient("x*y", r#"["w", "x"]"#).unwrap());
+        assert_eq!(g, ["0", "y"]);
+        // No variables: the sorted free symbols.
+        let g = json_list(&gradient("z*x + y", "").unwrap());
+        assert_eq!(g, ["z", "1", "x"]);
+        assert_eq!(gradient("z*x + y", "[]").unwrap(), gradient("z*x + y", "").unwrap());
+        assert_eq!(err_msg(gradient("x", r#"["2*x"]"#)), "'2*x' is not a symbol");
+    }
+
+    fn json_matrix(json: &str) -> Vec<Vec<String>> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn jacobian_matrix() {
+        let j = json_matrix(&jacobian(r#"["x**2*y", "5*x + sin(y)"]"#, r#"["x", "y"]"#).unwrap());
+        assert_eq!(j.len(), 2);
+        assert_expr(&j[0][0], "2*x*y");
+        assert_expr(&j[0][1], "x**2");
+        assert_expr(&j[1][0], "5");
+        assert_expr(&j[1][1], "cos(y)");
+        // One row per function, one column per variable.
+        let j = json_matrix(&jacobian(r#"["x*y*z"]"#, r#"["x", "y", "z"]"#).unwrap());
+        assert_eq!((j.len(), j[0].len()), (1, 3));
+        assert_eq!(err_msg(jacobian(r#"["x"]"#, r#"["x", "2*y"]"#)), "'2*y' is not a symbol");
+        assert!(err_msg(jacobian("[]", r#"["x"]"#)).starts_with("jacobian requires"));
+    }
+
+    #[wasm_bindgen_test]
+    fn taylor_export() {
+        assert_expr(&taylor("sin(x)/x", "x", "0", 4).unwrap(), "1 - x**2/6 + x**4/120");
+        assert_expr(&taylor("exp(-x**2)", "x", "0", 2).unwrap(), "1 - x**2");
+        assert_expr(&taylor("x**2", "x", "a", 2).unwrap(), "x**2");
+        assert_eq!(taylor("cos(x)", "x", "0", 0).unwrap(), "1");
+        let msg = err_msg(taylor("1/x", "x", "0", 2));
+        assert!(msg.starts_with("cannot expand") && msg.ends_with("domain error"), "{}", msg);
+    }
+
+    #[wasm_bindgen_test]
+    fn batch_evaluation() {
+        assert_eq!(eval_batch("x**2 + 1", "x", &[0.0, 1.5, -2.0]).unwrap(), vec![1.0, 3.25, 5.0]);
+        let v = eval_batch("sin(t)", "t", &[0.0, 1.0]).unwrap();
+        assert_eq!(v[0], 0.0);
+        assert_close(v[1], 1f64.sin());
+        // Complex and non-finite points are NaN; the rest still evaluate.
+        let v = eval_batch("sqrt(x)", "x", &[4.0, -1.0, 9.0]).unwrap();
+        assert_eq!((v[0], v[2]), (2.0, 3.0));
+        assert!(v[1].is_nan());
+        let v = eval_batch("log(x)", "x", &[1.0, -1.0, f64::NAN]).unwrap();
+        assert_eq!(v[0], 0.0);
+        assert!(v[1].is_nan() && v[2].is_nan());
+        assert!(eval_batch("x", "x", &[]).unwrap().is_empty());
+        assert_eq!(eval_batch("3", "x", &[1.0, 2.0]).unwrap(), vec![3.0, 3.0]);
+        let grid = eval_batch_multi("x*y + 1", "x", "y", &[1.0, 2.0, -3.0, 0.5]).unwrap();
+        assert_eq!(grid, vec![3.0, -0.5]);
+        let grid = eval_batch_multi("sqrt(x - y)", "x", "y", &[5.0, 1.0, 1.0, 5.0]).unwrap();
+        assert_eq!(grid[0], 2.0);
+        assert!(grid[1].is_nan());
+        assert_eq!(
+            err_msg(eval_batch("x + y", "x", &[1.0])),
+            "cannot evaluate numerically: unbound symbols y"
+        );
+        assert_eq!(
+            err_msg(eval_batch_multi("x*y", "x", "y", &[1.0, 2.0, 3.0])),
+            "pairs must contain an even number of values"
+        );
+    }
+
+    /// Compile generated source such as `function(x) { ... }` into a callable.
+    fn compile_js(src: &str) -> js_sys::Function {
+        js_sys::Function::new_no_args(&format!("return {};", src))
+            .call0(&JsValue::NULL)
+            .unwrap()
+            .into()
+    }
+
+    #[wasm_bindgen_test]
+    fn lambdify_to_javascript() {
+        let expr = "sin(x) + y**2/3";
+        let params = r#"["x", "y"]"#;
+        for style in ["function", "arrow"] {
+            let f = compile_js(&lambdify_js_with(expr, params, style, "").unwrap());
+            for (x, y) in [(0.0, 0.0), (0.5, -2.0), (2.0, 3.5)] {
+                let got = f.call2(&JsValue::NULL, &x.into(), &y.into()).unwrap();
+                let bindings = format!(r#"{{"x": {}, "y": {}}}"#, x, y);
+                assert_close(got.as_f64().unwrap(), eval_at(expr, &bindings).unwrap());
+            }
+        }
+        assert_eq!(lambdify_js(expr, params).unwrap(), lambdify_js_with(expr, params, "function", "").unwrap());
+        let esm = lambdify_js_with("x", r#"["x"]"#, "esm", "f").unwrap();
+        assert_eq!(esm, "export function f(x) { return x; }");
+        assert!(err_msg(lambdify_js("x + y", r#"["x"]"#)).ends_with("missing from the parameter list: y"));
+        assert_eq!(err_msg(lambdify_js("x", r#"["x", "x"]"#)), "duplicate parameter 'x'");
+        assert!(err_msg(lambdify_js_with("x", r#"["x"]"#, "lambda", "")).starts_with("unknown style"));
+    }
+
+    #[wasm_bindgen_test]
+    fn lambdify_rejects_bad_names() {
+        assert_eq!(
+            err_msg(lambdify_js_with("x", r#"["x"]"#, "esm", "")),
+            "style \"esm\" requires a function name"
+        );
+        for name in ["f(){}; alert(1); function g", "1f", "a-b", "f g", "class", "return"] {
+            assert_eq!(
+                err_msg(lambdify_js_with("x", r#"["x"]"#, "esm", name)),
+                format!("'{}' is not a valid JavaScript identifier", name)
+            );
+        }
+        assert!(lambdify_js_with("x", r#"["x"]"#, "esm", "$f_1").is_ok());
+        assert_eq!(
+            err_msg(lambdify_js("this", r#"["this"]"#)),
+            "'this' is not a valid JavaScript identifier"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn c_function_generation() {
+        let code = generate_c_function("x**2 + y**3", "kernel", r#"["x", "y"]"#).unwrap();
+        assert!(code.starts_with("/* Generated by SymEngine; requires #include <math.h>"));
+        assert!(code.contains("double kernel(double x, double y) {"), "{}", code);
+        assert!(code.contains("pow(x, 2)") && code.contains("pow(y, 3)"), "{}", code);
+        assert!(!code.contains("x*x"), "{}", code);
+        let code = generate_c_function_with("sin(t)", "f", r#"["t"]"#, true).unwrap();
+        assert!(code.contains("float f(float t) {"), "{}", code);
+        let code = generate_c_function("2", "two", "[]").unwrap();
+        assert!(code.contains("double two(void) {"), "{}", code);
+        assert!(err_msg(generate_c_function("x + y", "f", r#"["x"]"#))
+            .ends_with("missing from the parameter list: y"));
+        assert_eq!(err_msg(generate_c_function("x", "f", r#"["x + 1"]"#)), "'1 + x' is not a symbol");
+        assert_eq!(err_msg(generate_c_function("x", "f", r#"["x", "x"]"#)), "duplicate parameter 'x'");
+        assert_eq!(err_msg(generate_c_function("x", "2f", r#"["x"]"#)), "'2f' is not a valid C identifier");
+    }
+
+    #[wasm_bindgen_test]
+    fn expression_size() {
+        for atom in ["x", "7", "3/4", "pi", "I"] {
+            assert_eq!(count_ops(atom).unwrap(), 0, "{}", atom);
+            assert_eq!(num_args(atom).unwrap(), 0, "{}", atom);
+        }
+        // One Add node, however many terms.
+        assert_eq!(count_ops("x + y + z").unwrap(), 1);
+        assert_eq!(num_args("x + y + z").unwrap(), 3);
+        assert_eq!(count_ops("sin(cos(tan(x)))").unwrap(), 3);
+        assert_eq!(num_args("sin(cos(tan(x)))").unwrap(), 1);
+        assert_eq!(count_ops("x**2 + 2*x*y").unwrap(), 3);
+        assert!(count_ops(&expand("(x + y)**5").unwrap()).unwrap() > count_ops("(x + y)**5").unwrap());
+        assert!(count_ops("(").is_err());
+    }
+
+    /// Rebuild an expression string from `to_ast_json` output, checking each
+    /// leaf re-parses to a node of the recorded type.
+    fn rebuild(node: &serde_json::Value) -> String {
+        let ty = node["type"].as_str().unwrap();
+        if let Some(leaf) = node["name"].as_str().or(node["value"].as_str()) {
+            assert_eq!(get_type(leaf).unwrap(), ty, "leaf {}", leaf);
+            return format!("({})", leaf);
+        }
+        let args: Vec<String> = node["args"].as_array().unwrap().iter().map(rebuild).collect();
+        match ty {
+            "Add" => format!("({})", args.join(" + ")),
+            "Mul" => format!("({})", args.join("*")),
+            "Pow" => format!("({})**({})", args[0], args[1]),
+            _ => format!("{}({})", ty.to_lowercase(), args.join(", ")),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn ast_json() {
+        let ast: serde_json::Value = serde_json::from_str(&to_ast_json("x + 2", 10).unwrap()).unwrap();
+        assert_eq!(ast["type"], "Add");
+        let args = ast["args"].as_array().unwrap();
+        assert_eq!(args.len(), 2);
+        assert!(args.contains(&serde_json::json!({ "type": "Symbol", "name": "x" })));
+        assert!(args.contains(&serde_json::json!({ "type": "Integer", "value": "2" })));
+        for e in ["x + 2", "x*sin(y)**2 - 1/2", "exp(-x**2)/pi", "cos(x + 3*y) + 7/3"] {
+            let ast: serde_json::Value = serde_json::from_str(&to_ast_json(e, 100).unwrap()).unwrap();
+            assert!(expr_equal(&rebuild(&ast), e).unwrap(), "{}", e);
+        }
+        let ast: serde_json::Value = serde_json::from_str(&to_ast_json("x + sin(y)", 0).unwrap()).unwrap();
+        for arg in ast["args"].as_array().unwrap() {
+            assert_eq!(arg["truncated"], true);
+            assert!(arg.get("args").is_none());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn equality() {
+        assert!(expr_equal("x + y", "y + x").unwrap());
+        assert!(expr_equal("x*(y*z)", "(x*y)*z").unwrap());
+        assert!(!expr_equal("(x + 1)**2", "x**2 + 2*x + 1").unwrap());
+        assert!(expr_not_equal("(x + 1)**2", "x**2 + 2*x + 1").unwrap());
+        assert!(expr_equal_expanded("(x + 1)**2", "x**2 + 2*x + 1").unwrap());
+        assert!(!expr_equal("x", "y").unwrap());
+        assert!(expr_not_equal("x", "y").unwrap());
+        assert!(!expr_equal_expanded("(x + 1)**2", "x**2 + 1").unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    fn type_predicates() {
+        assert!(is_integer("3").unwrap());
+        assert!(is_integer("-6/2").unwrap());
+        assert!(!is_integer("3/2").unwrap());
+        assert!(is_rational("3/2").unwrap());
+        assert!(is_symbol("x").unwrap());
+        assert!(!is_symbol("2*x").unwrap());
+        assert!(is_number("1.5").unwrap());
+        assert!(!is_number("x").unwrap());
+        assert!(is_zero("x - x").unwrap());
+        assert!(is_positive("1/3").unwrap());
+        assert!(is_negative("-2").unwrap());
+        assert!(is_complex_number("2 + 3*I").unwrap());
+        assert!(!is_complex_number("2").unwrap());
+        // Non-numeric input is false rather than a trap.
+        for e in ["x + 1", "-x", "pi"] {
+            assert!(!is_zero(e).unwrap());
+            assert!(!is_positive(e).unwrap());
+            assert!(!is_negative(e).unwrap());
+            assert!(!is_complex_number(e).unwrap());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn hashing() {
+        let h = expr_hash("x + y*z").unwrap();
+        assert!(h.parse::<u64>().is_ok(), "{}", h);
+        assert_eq!(expr_hash("z*y + x").unwrap(), h);
+        assert_eq!(expr_hash("x + y*z").unwrap(), h);
+        assert_ne!(expr_hash("x - y*z").unwrap(), h);
+    }
+
+    #[wasm_bindgen_test]
+    fn type_names() {
+        let cases = [
+            ("x + y", "Add"),
+            ("x*y", "Mul"),
+            ("-x", "Mul"),
+            ("x**2", "Pow"),
+            ("exp(x)", "Pow"),
+            ("x", "Symbol"),
+            ("2", "Integer"),
+            ("1/2", "Rational"),
+            ("1.5", "RealDouble"),
+            ("2 + 3*I", "Complex"),
+            ("pi", "Constant"),
+            ("sin(x)", "Sin"),
+            ("log(x)", "Log"),
+            ("gamma(x)", "Gamma"),
+            ("abs(x)", "Abs"),
+            ("f(x)", "FunctionSymbol"),
+        ];
+        // Repeated calls exercise freeing the class-name string.
+        for _ in 0..100 {
+            for (e, ty) in cases {
+                assert_eq!(get_type(e).unwrap(), ty, "{}", e);
+            }
+        }
+    }
+
+    /// Check that `actual` holds exactly the expressions in `expected`, in
+    /// any order.
+    #[track_caller]
+    fn assert_same_set(actual: &[String], expected: &[&str]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for want in expected {
+            assert!(
+                actual.iter().any(|a| expr_equal_expanded(a, want).unwrap()),
+                "{} missing from {:?}",
+                want,
+                actual
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn top_level_args() {
+        assert!(json_list(&get_args("x").unwrap()).is_empty());
+        assert!(json_list(&get_args("42").unwrap()).is_empty());
+        assert_same_set(&json_list(&get_args("x + y*z").unwrap()), &["x", "y*z"]);
+        assert_eq!(json_list(&get_args("x**2").unwrap()), ["x", "2"]);
+        assert_same_set(&json_list(&get_args("2*x*sin(y)").unwrap()), &["2", "x", "sin(y)"]);
+        assert_eq!(json_list(&get_args("atan2(y, x)").unwrap()), ["y", "x"]);
+        assert_eq!(get_type("x + y*z").unwrap(), "Add");
+        for arg in json_list(&get_args("x**2 + sin(x) + 1").unwrap()) {
+            assert!(expand(&arg).is_ok(), "{}", arg);
+        }
+        assert!(get_args("").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn caret_as_power() {
+        assert_expr(&parse_expr("x^2 + 1", true).unwrap(), "x**2 + 1");
+        assert_eq!(parse_expr("x^2 + 1", true).unwrap(), parse_expr("x**2 + 1", false).unwrap());
+        assert_expr(&expand_with("(x + 1)^2", true).unwrap(), "x**2 + 2*x + 1");
+        assert_expr(&differentiate_with("x^3", "x", true).unwrap(), "3*x**2");
+        let v = evalf_with("2^(1/2)", true).unwrap();
+        assert_close(eval_double(&v).unwrap(), std::f64::consts::SQRT_2);
+    }
+
+    #[wasm_bindgen_test]
+    fn bad_input_is_a_js_error() {
+        let bad = ["x +* 2", "(x + 1", "x + 1)", "sin(x))", "[x", "(x]", "[x)", "f(x,)", "x y", "", "   ", "x\0y"];
+        for input in bad {
+            assert!(expand(input).is_err(), "expand({:?})", input);
+            assert_still_works();
+            assert!(differentiate(input, "x").is_err(), "differentiate({:?})", input);
+            assert_still_works();
+            assert!(substitute(input, "x", "1").is_err(), "substitute({:?})", input);
+            assert!(substitute("x", "x", input).is_err(), "substitute value {:?}", input);
+            assert_still_works();
+            assert!(evalf(input).is_err(), "evalf({:?})", input);
+            assert_still_works();
+            assert!(sym_sin(input).is_err(), "sin({:?})", input);
+            assert!(add("x", input).is_err(), "add({:?})", input);
+            assert_still_works();
+            assert!(matrix_det(1, 1, input).is_err(), "matrix_det({:?})", input);
+            assert!(matrix_det_json(&format!(r#"[[{:?}]]"#, input)).is_err(), "matrix_det_json({:?})", input);
+            assert_still_works();
+        }
+        assert_eq!(err_msg(expand("(x + 1")), r#"could not parse expression "(x + 1""#);
+        assert_eq!(err_msg(expand("")), "empty expression");
+    }
+
+    #[wasm_bindgen_test]
+    fn bad_variable_names_are_js_errors() {
+        for var in ["", "   ", "a\0b"] {
+            assert!(differentiate("x", var).is_err(), "{:?}", var);
+            assert!(differentiate_n("x", var, 2).is_err(), "{:?}", var);
+            assert!(taylor("x", var, "0", 2).is_err(), "{:?}", var);
+            assert!(substitute("x", var, "1").is_err(), "{:?}", var);
+            assert!(eval_batch_multi("1", var, "y", &[0.0, 0.0]).is_err(), "{:?}", var);
+            assert!(solve_poly("x", var).is_err(), "{:?}", var);
+            assert!(solve_poly_json("x", var).is_err(), "{:?}", var);
+            assert!(coeff("x", var, 1).is_err(), "{:?}", var);
+            assert!(poly_degree("x", var).is_err(), "{:?}", var);
+            assert!(poly_coeffs("x", var).is_err(), "{:?}", var);
+            assert!(differentiate_with("x", var, true).is_err(), "{:?}", var);
+            assert_still_works();
+        }
+        assert_eq!(err_msg(differentiate("x", "")), r#"invalid symbol name "": empty expression"#);
+        assert!(err_msg(differentiate("x", "a\0b")).ends_with("NUL byte at offset 1"));
+    }
+
+    /// Compare a JSON matrix with expected entries, entry by entry after
+    /// expanding.
+    #[track_caller]
+    fn assert_matrix(json: &str, expected: &[&[&str]]) {
+        let m = json_matrix(json);
+        assert_eq!(m.len(), expected.len(), "rows of {}", json);
+        for (row, want) in m.iter().zip(expected) {
+            assert_eq!(row.len(), want.len(), "columns of {}", json);
+            for (e, w) in row.iter().zip(want.iter()) {
+                assert_expr(e, w);
+            }
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn json_matrix_input() {
+        // Entries may contain commas.
+        let m = r#"[["beta(a, b)", "1"], ["0", "atan2(y, x)"]]"#;
+        assert_expr(&matrix_det_json(m).unwrap(), "beta(a, b)*atan2(y, x)");
+        assert_matrix(
+            &matrix_transpose_json(m).unwrap(),
+            &[&["beta(a, b)", "0"], &["1", "atan2(y, x)"]],
+        );
+        assert_eq!(
+            err_msg(matrix_det_json(r#"[["1", "2"], ["3"]]"#)),
+            "ragged matrix: row 1 has 1 entries, expected 2"
+        );
+        for empty in ["[]", "[[]]", "[[], []]"] {
+            assert_eq!(
+                err_msg(matrix_det_json(empty)),
+                "matrix must have at least one row and one column",
+                "{}",
+                empty
+            );
+        }
+        assert!(err_msg(matrix_det_json(r#"["a", "b"]"#)).starts_with("expected a JSON array of arrays of strings"));
+        assert!(err_msg(matrix_inv_json(r#"[[1, 2]]"#)).starts_with("expected a JSON array of arrays of strings"));
+        assert!(matrix_mul_json(r#"[["("]]"#, r#"[["1"]]"#).is_err());
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_json_results() {
+        let a = r#"[["a", "b", "c"], ["d", "e", "f"]]"#;
+        // Row-major: the outer array holds rows.
+        let t = matrix_transpose_json(a).unwrap();
+        assert_eq!(json_matrix(&t), [["a", "d"], ["b", "e"], ["c", "f"]]);
+        for entry in json_matrix(&t).iter().flatten() {
+            assert!(symengine::Expr::try_parse(entry).is_ok(), "{}", entry);
+        }
+        let p = matrix_mul_json(a, r#"[["1"], ["x"], ["x**2"]]"#).unwrap();
+        assert_matrix(&p, &[&["a + b*x + c*x**2"], &["d + e*x + f*x**2"]]);
+        let inv = matrix_inv_json(r#"[["1", "2"], ["3", "4"]]"#).unwrap();
+        assert_matrix(&inv, &[&["-2", "1"], &["3/2", "-1/2"]]);
+        assert!(err_msg(matrix_mul_json(a, a)).contains("incompatible shapes 2x3 and 2x3"));
+        assert_eq!(
+            err_msg(matrix_transpose_json("[]")),
+            "matrix must have at least one row and one column"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_addition() {
+        let a = r#"[["x", "y", "1"], ["2", "z", "w"]]"#;
+        let b = r#"[["-x", "y", "0"], ["1", "-z", "w"]]"#;
+        let sum = matrix_add(a, b).unwrap();
+        assert_eq!(json_matrix(&sum), [["0", "2*y", "1"], ["3", "0", "2*w"]]);
+        assert_eq!(
+            err_msg(matrix_add(a, r#"[["1", "2"], ["3", "4"]]"#)),
+            "cannot add matrices: incompatible shapes 2x3 and 2x2"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_subtraction() {
+        let m = r#"[["x", "sin(y)"], ["1/2", "z**2"]]"#;
+        assert_eq!(json_matrix(&matrix_sub(m, m).unwrap()), [["0", "0"], ["0", "0"]]);
+        let a = r#"[["1", "2"], ["3", "4"]]"#;
+        let third = matrix_scale(a, "1/3").unwrap();
+        let d = matrix_sub(a, &third).unwrap();
+        assert_eq!(json_matrix(&d), [["2/3", "4/3"], ["2", "8/3"]]);
+        assert_eq!(
+            err_msg(matrix_sub(a, r#"[["1", "2"]]"#)),
+            "cannot subtract matrices: incompatible shapes 2x2 and 1x2"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_scaling() {
+        let m = r#"[["2", "x"], ["1/3", "y + 1"]]"#;
+        assert_matrix(&matrix_scale(m, "3/2").unwrap(), &[&["3", "3*x/2"], &["1/2", "3*y/2 + 3/2"]]);
+        assert_matrix(&matrix_scale(m, "1/det").unwrap(), &[&["2/det", "x/det"], &["1/(3*det)", "(y + 1)/det"]]);
+        assert_eq!(json_matrix(&matrix_scale(m, "0").unwrap()), [["0", "0"], ["0", "0"]]);
+        assert!(matrix_scale(m, "x +* 1").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn rank() {
+        assert_eq!(matrix_rank(r#"[["1", "2"], ["3", "4"]]"#).unwrap(), 2);
+        assert_eq!(matrix_rank(r#"[["1", "2", "3"], ["2", "4", "6"], ["1", "1", "1"]]"#).unwrap(), 2);
+        assert_eq!(matrix_rank(r#"[["0", "0"], ["0", "0"]]"#).unwrap(), 0);
+        // The rows are equal only once (x + 1)**2 is expanded.
+        let m = r#"[["(x + 1)**2", "1"], ["x**2 + 2*x + 1", "1"]]"#;
+        assert_eq!(matrix_rank(m).unwrap(), 1);
+        assert_eq!(matrix_rank(r#"[["a", "b"], ["c", "d"]]"#).unwrap(), 2);
+        assert_eq!(matrix_rank(r#"[["1", "2", "3"]]"#).unwrap(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn linear_solve() {
+        // Numeric A, symbolic right-hand side: A·x expands back to b.
+        let a = r#"[["2", "1", "0"], ["1", "3", "1"], ["0", "1", "4"]]"#;
+        let b = r#"[["p"], ["q"], ["r"]]"#;
+        let x = matrix_solve(a, b).unwrap();
+        assert_matrix(&matrix_mul_json(a, &x).unwrap(), &[&["p"], &["q"], &["r"]]);
+        // Symbolic A: check the solution after substituting a value.
+        let a = r#"[["a", "1", "0"], ["1", "a", "1"], ["0", "1", "a"]]"#;
+        let b = r#"[["1"], ["0"], ["0"]]"#;
+        let x = matrix_solve(a, b).unwrap();
+        let at = r#"{"a": "3"}"#;
+        let ax = matrix_mul_json(&matrix_substitute(a, at).unwrap(), &matrix_substitute(&x, at).unwrap());
+        assert_matrix(&ax.unwrap(), &[&["1"], &["0"], &["0"]]);
+        // Several right-hand sides, and a zero leading pivot.
+        let x = matrix_solve(r#"[["0", "1"], ["1", "0"]]"#, r#"[["1", "2"], ["3", "4"]]"#).unwrap();
+        assert_eq!(json_matrix(&x), [["3", "4"], ["1", "2"]]);
+        assert!(err_msg(matrix_solve(r#"[["1", "2"], ["2", "4"]]"#, r#"[["1"], ["2"]]"#)).contains("singular"));
+        assert!(matrix_solve(r#"[["1", "2"]]"#, r#"[["1"]]"#).is_err());
+        assert!(matrix_solve(r#"[["1", "0"], ["0", "1"]]"#, r#"[["1"]]"#).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn eigenvalues() {
+        let eig = |m: &str| json_list(&matrix_eigenvalues(m).unwrap());
+        // Symbolic roots may keep radicals like sqrt(b**2), so compare them
+        // after substituting values.
+        let eig_at = |m: &str, at: &str| -> Vec<String> {
+            eig(m).iter().map(|e| substitute_many(e, at).unwrap()).collect()
+        };
+        assert_same_set(&eig(r#"[["2", "0"], ["0", "3"]]"#), &["2", "3"]);
+        assert_same_set(&eig(r#"[["2", "1"], ["1", "2"]]"#), &["1", "3"]);
+        let ab = r#"[["a", "b"], ["b", "a"]]"#;
+        assert_same_set(&eig_at(ab, r#"{"a": "5", "b": "2"}"#), &["3", "7"]);
+        // The characteristic-polynomial symbol must not capture λ or lambda.
+        let m = r#"[["a", "λ"], ["λ", "a"]]"#;
+        assert_same_set(&eig_at(m, r#"{"a": "5", "λ": "2"}"#), &["3", "7"]);
+        let m = r#"[["lambda", "1"], ["0", "2"]]"#;
+        assert_same_set(&eig_at(m, r#"{"lambda": "5"}"#), &["5", "2"]);
+        let mut diag: Vec<f64> = eig(r#"[["1", "0", "0"], ["0", "2", "0"], ["0", "0", "3"]]"#)
+            .iter()
+            .map(|e| {
+                let z = eval_complex(e).unwrap();
+                assert!(z[1].abs() < 1e-9, "{} is not real", e);
+                z[0]
+            })
+            .collect();
+        diag.sort_by(f64::total_cmp);
+        assert_eq!(diag.len(), 3);
+        for (v, want) in diag.iter().zip([1.0, 2.0, 3.0]) {
+            assert!((v - want).abs() < 1e-9, "{:?}", diag);
+        }
+        // A repeated eigenvalue is listed once.
+        assert_same_set(&eig(r#"[["1", "1"], ["0", "1"]]"#), &["1"]);
+        assert_eq!(
+            err_msg(matrix_eigenvalues(r#"[["1", "2", "3"]]"#)),
+            "expected a square matrix, got 1x3"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn reduced_row_echelon_form() {
+        let rref = |m: &str| matrix_rref(m).unwrap();
+        assert_matrix(&rref(r#"[["1", "2"], ["3", "4"]]"#), &[&["1", "0"], &["0", "1"]]);
+        assert_matrix(&rref(r#"[["1", "2", "3"], ["4", "5", "6"]]"#), &[&["1", "0", "-1"], &["0", "1", "2"]]);
+        assert_matrix(&rref(r#"[["1/2", "1/3"], ["1/4", "1/5"]]"#), &[&["1", "0"], &["0", "1"]]);
+        // A zero in the first pivot position needs a row swap.
+        assert_matrix(&rref(r#"[["0", "1", "2"], ["1", "0", "3"]]"#), &[&["1", "0", "3"], &["0", "1", "2"]]);
+        assert_matrix(&rref(r#"[["1", "2"], ["2", "4"]]"#), &[&["1", "2"], &["0", "0"]]);
+        // Symbolic pivots are assumed nonzero.
+        assert_matrix(&rref(r#"[["a", "b"], ["0", "1"]]"#), &[&["1", "0"], &["0", "1"]]);
+        assert_matrix(&rref(r#"[["a", "a*b"]]"#), &[&["1", "b"]]);
+    }
+
+    /// Factors named `keys` from a JSON object result such as `matrix_lu`'s,
+    /// re-encoded as JSON matrices.
+    fn factors(json: &str, keys: &[&str]) -> Vec<String> {
+        let v: serde_json::Value = serde_json::from_str(json).unwrap();
+        keys.iter().map(|k| v[k].to_string()).collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn lu_factorization() {
+        let a = r#"[["2", "1", "1"], ["4", "3", "3"], ["8", "7", "9"]]"#;
+        let lu = factors(&matrix_lu(a).unwrap(), &["L", "U"]);
+        assert_matrix(&lu[0], &[&["1", "0", "0"], &["2", "1", "0"], &["4", "3", "1"]]);
+        assert!(matrix_equal(&matrix_mul_json(&lu[0], &lu[1]).unwrap(), a).unwrap());
+        let s = r#"[["a", "1", "0"], ["1", "a", "1"], ["0", "1", "a"]]"#;
+        let lu = factors(&matrix_lu(s).unwrap(), &["L", "U"]);
+        let product = matrix_mul_json(&lu[0], &lu[1]).unwrap();
+        for at in [r#"{"a": "3"}"#, r#"{"a": "-5/2"}"#] {
+            let got = matrix_substitute(&product, at).unwrap();
+            assert!(matrix_equal_expanded(&got, &matrix_substitute(s, at).unwrap()).unwrap(), "{}", at);
+        }
+        assert_eq!(err_msg(matrix_lu(r#"[["1", "2"]]"#)), "expected a square matrix, got 1x2");
+        assert_eq!(
+            err_msg(matrix_lu(r#"[["0", "1"], ["1", "0"]]"#)),
+            "factorization needs row swaps (zero pivot)"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn ldl_factorization() {
+        let a = r#"[["4", "2", "2"], ["2", "5", "3"], ["2", "3", "6"]]"#;
+        let ld = factors(&matrix_ldl(a).unwrap(), &["L", "D"]);
+        let lt = matrix_transpose_json(&ld[0]).unwrap();
+        let product = matrix_mul_json(&matrix_mul_json(&ld[0], &ld[1]).unwrap(), &lt).unwrap();
+        assert!(matrix_equal(&product, a).unwrap());
+        let d = json_matrix(&ld[1]);
+        assert_eq!((d[0][1].as_str(), d[1][0].as_str()), ("0", "0"));
+        let r = r#"[["1/2", "1/3"], ["1/3", "1/4"]]"#;
+        let ld = factors(&matrix_ldl(r).unwrap(), &["L", "D"]);
+        assert_matrix(&ld[1], &[&["1/2", "0"], &["0", "1/36"]]);
+        assert_eq!(
+            err_msg(matrix_ldl(r#"[["1", "2"], ["3", "4"]]"#)),
+            "matrix is not symmetric"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn fraction_free_lu() {
+        // Diagonally dominant, so no pivot is zero.
+        let rows: Vec<Vec<String>> = (0..6)
+            .map(|i| (0..6).map(|j| if i == j { 60 } else { i + 2 * j + 1 }.to_string()).collect())
+            .collect();
+        let a = serde_json::to_string(&rows).unwrap();
+        let fflu = factors(&matrix_fflu(&a).unwrap(), &["LU"]);
+        let fflu = json_matrix(&fflu[0]);
+        assert!(fflu.iter().flatten().all(|e| is_integer(e).unwrap()), "{:?}", fflu);
+        // Plain LU of the same matrix has fractional entries.
+        let lu = factors(&matrix_lu(&a).unwrap(), &["L", "U"]);
+        assert!(lu.iter().any(|m| json_matrix(m).iter().flatten().any(|e| !is_integer(e).unwrap())));
+        let ldu = factors(&matrix_ffldu(&a).unwrap(), &["L", "D", "U"]);
+        for m in &ldu {
+            assert!(json_matrix(m).iter().flatten().all(|e| is_integer(e).unwrap()), "{}", m);
+        }
+        assert_eq!(err_msg(matrix_fflu(r#"[["1", "2"]]"#)), "expected a square matrix, got 1x2");
+        assert_eq!(err_msg(matrix_ffldu(r#"[["1"], ["2"]]"#)), "expected a square matrix, got 2x1");
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_constructors() {
+        assert_eq!(json_matrix(&matrix_identity(2).unwrap()), [["1", "0"], ["0", "1"]]);
+        assert_eq!(json_matrix(&matrix_zeros(1, 3).unwrap()), [["0", "0", "0"]]);
+        assert_eq!(json_matrix(&matrix_ones(2, 1).unwrap()), [["1"], ["1"]]);
+        let m = r#"[["a", "sin(b)", "1/2"], ["c**2", "d", "e + f"], ["0", "g", "h"]]"#;
+        let id = matrix_identity(3).unwrap();
+        assert!(matrix_equal(&matrix_mul_json(&id, m).unwrap(), m).unwrap());
+        assert!(matrix_equal(&matrix_mul_json(m, &id).unwrap(), m).unwrap());
+        let d = json_matrix(&matrix_diag(r#"["x", "y + 1", "z"]"#).unwrap());
+        for (r, row) in d.iter().enumerate() {
+            for (c, e) in row.iter().enumerate() {
+                if r != c {
+                    assert_eq!(e, "0");
+                }
+            }
+        }
+        assert_expr(&d[1][1], "y + 1");
+        let big = json_matrix(&matrix_identity(20).unwrap());
+        assert_eq!((big.len(), big[19].len(), big[19][19].as_str()), (20, 20, "1"));
+        assert!(matrix_identity(0).is_err());
+        assert!(matrix_zeros(2, 0).is_err());
+        assert!(matrix_diag("[]").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_concatenation() {
+        let a = r#"[["a", "b", "c"], ["d", "e", "f"]]"#;
+        let b = r#"[["p"], ["q"]]"#;
+        let h = json_matrix(&matrix_hstack(a, b).unwrap());
+        assert_eq!(h, [["a", "b", "c", "p"], ["d", "e", "f", "q"]]);
+        let v = json_matrix(&matrix_vstack(a, r#"[["x", "y", "z"]]"#).unwrap());
+        assert_eq!(v, [["a", "b", "c"], ["d", "e", "f"], ["x", "y", "z"]]);
+        assert_eq!(
+            err_msg(matrix_hstack(a, r#"[["p"]]"#)),
+            "incompatible shapes 2x3 and 1x1"
+        );
+        assert_eq!(err_msg(matrix_vstack(a, b)), "incompatible shapes 2x3 and 2x1");
+    }
+
+    #[wasm_bindgen_test]
+    fn row_and_column_deletion() {
+        let m = r#"[["a", "b", "c"], ["d", "e", "f"]]"#;
+        assert_eq!(json_matrix(&matrix_delete_row(m, 0).unwrap()), [["d", "e", "f"]]);
+        assert_eq!(json_matrix(&matrix_delete_col(m, 1).unwrap()), [["a", "c"], ["d", "f"]]);
+        assert_eq!(json_matrix(&matrix_delete_col(m, 2).unwrap()), [["a", "b"], ["d", "e"]]);
+        // Deleting the only row gives 0×n, written []; deleting the only
+        // column keeps one empty array per row.
+        assert_eq!(matrix_delete_row(r#"[["a", "b"]]"#, 0).unwrap(), "[]");
+        assert_eq!(matrix_delete_col(r#"[["a"], ["b"]]"#, 0).unwrap(), "[[],[]]");
+        assert_eq!(matrix_delete_col(r#"[["a"]]"#, 0).unwrap(), "[[]]");
+        assert_eq!(err_msg(matrix_delete_row(m, 2)), "index 2 out of range for length 2");
+        assert_eq!(err_msg(matrix_delete_col(m, 3)), "index 3 out of range for length 3");
+    }
+
+    #[wasm_bindgen_test]
+    fn element_get_and_set() {
+        let m = r#"[["a", "b", "c"], ["d", "e", "f"]]"#;
+        assert_eq!(matrix_get(m, 0, 0).unwrap(), "a");
+        assert_eq!(matrix_get(m, 0, 2).unwrap(), "c");
+        assert_eq!(matrix_get(m, 1, 0).unwrap(), "d");
+        assert_eq!(matrix_get(m, 1, 2).unwrap(), "f");
+        let s = json_matrix(&matrix_set(m, 1, 2, "x**2").unwrap());
+        assert_eq!(s, [["a", "b", "c"], ["d", "e", "x**2"]]);
+        let s = json_matrix(&matrix_set(m, 0, 0, "0").unwrap());
+        assert_eq!(s[0][0], "0");
+        assert_eq!(err_msg(matrix_get(m, 2, 0)), "position (2, 0) is outside the 2x3 matrix");
+        assert_eq!(err_msg(matrix_set(m, 0, 3, "1")), "position (0, 3) is outside the 2x3 matrix");
+        assert!(matrix_set(m, 0, 0, "(").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_equality() {
+        let a = r#"[["x + 1", "y"], ["0", "z"]]"#;
+        assert!(matrix_equal(a, r#"[["1 + x", "y"], ["0", "z"]]"#).unwrap());
+        assert!(!matrix_equal(a, r#"[["x", "y"], ["0", "z"]]"#).unwrap());
+        let sq = r#"[["(x + 1)**2"]]"#;
+        let ex = r#"[["x**2 + 2*x + 1"]]"#;
+        assert!(!matrix_equal(sq, ex).unwrap());
+        assert!(matrix_equal_expanded(sq, ex).unwrap());
+        // Different shapes compare unequal rather than erroring.
+        assert!(!matrix_equal(r#"[["1", "2"]]"#, r#"[["1"], ["2"]]"#).unwrap());
+        assert!(!matrix_equal_expanded(r#"[["1", "2"]]"#, r#"[["1"], ["2"]]"#).unwrap());
+        let m = r#"[["2", "1"], ["7", "4"]]"#;
+        let id = matrix_mul_json(m, &matrix_inv_json(m).unwrap()).unwrap();
+        assert!(matrix_equal(&id, &matrix_identity(2).unwrap()).unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_powers() {
+        let fib = r#"[["1", "1"], ["1", "0"]]"#;
+        assert_eq!(json_matrix(&matrix_pow(fib, 10).unwrap()), [["89", "55"], ["55", "34"]]);
+        assert_eq!(json_matrix(&matrix_pow(fib, 1).unwrap()), [["1", "1"], ["1", "0"]]);
+        assert_eq!(json_matrix(&matrix_pow(fib, 0).unwrap()), [["1", "0"], ["0", "1"]]);
+        let s = r#"[["a", "b"], ["c", "d"]]"#;
+        assert!(matrix_equal_expanded(&matrix_pow(s, 2).unwrap(), &matrix_mul_json(s, s).unwrap()).unwrap());
+        let cube = matrix_mul_json(&matrix_mul_json(s, s).unwrap(), s).unwrap();
+        assert!(matrix_equal_expanded(&matrix_pow(s, 3).unwrap(), &cube).unwrap());
+        assert_eq!(err_msg(matrix_pow(r#"[["1", "2"]]"#, 2)), "expected a square matrix, got 1x2");
+    }
+
+    #[wasm_bindgen_test]
+    fn kronecker_product() {
+        let a = r#"[["1", "2"], ["3", "4"]]"#;
+        let b = r#"[["x", "y", "z"]]"#;
+        let k = json_matrix(&matrix_kron(a, b).unwrap());
+        assert_eq!((k.len(), k[0].len()), (2, 6));
+        assert_eq!(k[0], ["x", "y", "z", "2*x", "2*y", "2*z"]);
+        assert_eq!(k[1][5], "4*z");
+        // kron(I2, A) is block diagonal with copies of A.
+        let s = r#"[["p", "q"], ["r", "s"]]"#;
+        let k = json_matrix(&matrix_kron(&matrix_identity(2).unwrap(), s).unwrap());
+        assert_eq!(
+            k,
+            [
+                ["p", "q", "0", "0"],
+                ["r", "s", "0", "0"],
+                ["0", "0", "p", "q"],
+                ["0", "0", "r", "s"],
+            ]
+        );
+        // A 1×1 factor scales.
+        let k = matrix_kron(r#"[["c"]]"#, s).unwrap();
+        assert!(matrix_equal(&k, &matrix_scale(s, "c").unwrap()).unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_differentiation() {
+        let rot = r#"[["cos(t)", "-sin(t)"], ["sin(t)", "cos(t)"]]"#;
+        let d = matrix_diff(rot, "t").unwrap();
+        assert!(matrix_equal(&d, r#"[["-sin(t)", "-cos(t)"], ["cos(t)", "-sin(t)"]]"#).unwrap());
+        let d = json_matrix(&matrix_diff(r#"[["x*t", "y"], ["5", "t**2"]]"#, "t").unwrap());
+        assert_eq!(d, [["x", "0"], ["0", "2*t"]]);
+        assert_eq!(err_msg(matrix_diff(rot, "2*t")), "'2*t' is not a symbol");
+    }
+
+    #[wasm_bindgen_test]
+    fn dot_and_cross_products() {
+        assert_expr(&vec_dot(r#"["a", "b", "c"]"#, r#"["x", "y", "z"]"#).unwrap(), "a*x + b*y + c*z");
+        assert_eq!(vec_dot(r#"["1", "2"]"#, r#"["3", "4"]"#).unwrap(), "11");
+        let a = r#"["a1", "a2", "a3"]"#;
+        let b = r#"["b1", "b2", "b3"]"#;
+        let ab = json_list(&vec_cross(a, b).unwrap());
+        let ba = json_list(&vec_cross(b, a).unwrap());
+        for (x, y) in ab.iter().zip(&ba) {
+            assert_expr(&neg(x).unwrap(), y);
+        }
+        let ab_json = serde_json::to_string(&ab).unwrap();
+        assert_eq!(expand(&vec_dot(&ab_json, a).unwrap()).unwrap(), "0");
+        assert_eq!(expand(&vec_dot(&ab_json, b).unwrap()).unwrap(), "0");
+        assert_eq!(json_list(&vec_cross(r#"["1", "0", "0"]"#, r#"["0", "1", "0"]"#).unwrap()), ["0", "0", "1"]);
+        assert_eq!(
+            err_msg(vec_dot(r#"["1", "2"]"#, r#"["1"]"#)),
+            "vectors have different lengths 2 and 1"
+        );
+        assert_eq!(
+            err_msg(vec_cross(r#"["1", "2"]"#, r#"["1", "2", "3"]"#)),
+            "cross product needs 3-component vectors, got 2"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn elementwise_operations() {
+        let squares = r#"[["(x + 1)**2", "(x - y)**2"], ["(2*a)**2", "3"]]"#;
+        let e = json_matrix(&matrix_apply(squares, "expand").unwrap());
+        assert_eq!(e[0][0], expand("(x + 1)**2").unwrap());
+        assert_eq!(e[0][1], expand("(x - y)**2").unwrap());
+        assert_eq!(e[1], ["4*a**2", "3"]);
+        let n = json_matrix(&matrix_apply(r#"[["pi", "1/4"], ["2*pi", "0"]]"#, "evalf").unwrap());
+        assert_close(eval_double(&n[0][0]).unwrap(), std::f64::consts::PI);
+        assert_close(eval_double(&n[0][1]).unwrap(), 0.25);
+        assert_close(eval_double(&n[1][0]).unwrap(), std::f64::consts::TAU);
+        assert_eq!(json_matrix(&matrix_apply(r#"[["x"]]"#, "sin").unwrap()), [["sin(x)"]]);
+        assert_eq!(
+            err_msg(matrix_apply(r#"[["x"]]"#, "frobnicate")),
+            format!("unknown operation 'frobnicate'; expected one of: {}", MATRIX_OPS)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn frobenius_norms() {
+        let m = r#"[["1", "-2"], ["2", "4"]]"#;
+        assert_eq!(matrix_frobenius_norm_squared(m).unwrap(), "25");
+        assert_eq!(matrix_frobenius_norm(m).unwrap(), "5");
+        // |3 + 4i|² is 25, not (3 + 4i)² = -7 + 24i.
+        let c = r#"[["3 + 4*I"]]"#;
+        assert_eq!(matrix_frobenius_norm_squared(c).unwrap(), "25");
+        assert_eq!(matrix_frobenius_norm(c).unwrap(), "5");
+        assert_eq!(matrix_frobenius_norm_squared(r#"[["1/2", "1/3"]]"#).unwrap(), "13/36");
+        let s = matrix_frobenius_norm_squared(r#"[["x", "y"]]"#).unwrap();
+        assert_expr(&s, "abs(x)**2 + abs(y)**2");
+        assert_expr(&matrix_frobenius_norm(r#"[["x", "y"]]"#).unwrap(), "sqrt(abs(x)**2 + abs(y)**2)");
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_substitution() {
+        let j = jacobian(r#"["x**2*y", "x*z", "sin(y) + z"]"#, r#"["x", "y", "z"]"#).unwrap();
+        let at = matrix_substitute(&j, r#"{"x": "2", "y": "3", "z": "5"}"#).unwrap();
+        let at = json_matrix(&at);
+        assert_eq!(at[0], ["12", "4", "0"]);
+        assert_eq!(at[1], ["5", "0", "2"]);
+        assert_eq!(at[2][1], "cos(3)");
+        let swapped = matrix_substitute(r#"[["x", "2*y"], ["x*y", "1"]]"#, r#"{"x": "y", "y": "x"}"#).unwrap();
+        assert!(matrix_equal(&swapped, r#"[["y", "2*x"], ["x*y", "1"]]"#).unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    fn big_integers() {
+        let digits = "9876543210".repeat(10);
+        assert_eq!(integer_from_string(&digits).unwrap(), digits);
+        assert_eq!(integer_from_string(" -12 ").unwrap(), "-12");
+        assert_eq!(integer_from_string("9223372036854775808").unwrap(), "9223372036854775808");
+        assert_eq!(err_msg(integer_from_string("12x")), "'12x' is not a decimal integer");
+        assert!(integer_from_string("").is_err());
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn complex_constructor() {
+        assert_eq!(complex("3", "4").unwrap(), "3 + 4*I");
+        assert_eq!(complex("3", "-4").unwrap(), "3 - 4*I");
+        assert_eq!(complex("5", "0").unwrap(), "5");
+        assert_eq!(complex("0", "1").unwrap(), "I");
+        assert_eq!(expand(&format!("abs({})", complex("3", "4").unwrap())).unwrap(), "5");
+        assert!(complex("(", "1").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn symbol_lists() {
+        assert_eq!(json_list(&make_symbols("x0:3, t").unwrap()), ["x0", "x1", "x2", "t"]);
+        assert_eq!(err_msg(make_symbols("a a")), "duplicate symbol name \"a\"");
+        assert_eq!(err_msg(make_symbols("a*b")), "invalid symbol name \"a*b\"");
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn function_symbols() {
+        assert_eq!(func("f", r#"["x", "y"]"#).unwrap(), "f(x, y)");
+        assert_eq!(func("u", r#"["t**2"]"#).unwrap(), "u(t**2)");
+        assert_eq!(err_msg(func("2f", r#"["x"]"#)), "invalid function name '2f'");
+        assert!(func("f", "x").is_err());
+        let f = func("f", r#"["x"]"#).unwrap();
+        assert!(differentiate(&f, "x").unwrap().contains("f(x)"));
+        assert_eq!(substitute(&f, "x", "2").unwrap(), "f(2)");
+    }
+
+    #[wasm_bindgen_test]
+    fn cse_as_json() {
+        let out: serde_json::Value =
+            serde_json::from_str(&cse(r#"["sin(a)**2 + sin(a)", "2*sin(a)"]"#).unwrap()).unwrap();
+        let replacements = out["replacements"].as_array().unwrap();
+        assert_eq!(replacements.len(), 1);
+        assert_eq!(replacements[0][1], "sin(a)");
+        let x0 = replacements[0][0].as_str().unwrap();
+        assert_eq!(out["reduced"][1], format!("2*{}", x0));
+        let out: serde_json::Value = serde_json::from_str(&cse(r#"["x + 1"]"#).unwrap()).unwrap();
+        assert_eq!(out, serde_json::json!({ "replacements": [], "reduced": ["1 + x"] }));
+        assert!(cse("[").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_errors_name_the_problem() {
+        assert_eq!(err_msg(expand("\0")), "expression contains a NUL byte at offset 0");
+        assert_still_works();
+        assert_eq!(err_msg(expand("   ")), "empty expression");
+        assert_still_works();
+        assert_eq!(err_msg(parse_expr("x^", true)), r#"could not parse expression "x^""#);
+        assert_still_works();
+        assert_eq!(expand(" (x + 1)*(x - 1) ").unwrap(), "-1 + x**2");
+    }
+
+    #[wasm_bindgen_test]
+    fn evaluation_errors() {
+        for input in ["log(0)", "gamma(-3)", "zeta(1)"] {
+            let printed = parse_expr(input, false).unwrap();
+            let expected = format!("cannot evaluate {}: domain error", printed);
+            assert_eq!(err_msg(evalf(input)), expected);
+            assert_eq!(err_msg(evalf_with(input, true)), expected);
+            assert_eq!(err_msg(evalf_prec(input, 53, false)), expected);
+            assert_still_works();
+        }
+        let expected = "cannot evaluate 1 + x: result still contains free symbols";
+        assert_eq!(err_msg(evalf("x + 1")), expected);
+        assert_eq!(err_msg(evalf_with("x^1 + 1", true)), expected);
+        assert_eq!(err_msg(evalf_prec("1 + x", 30, true)), expected);
+        assert_still_works();
+        assert_close(eval_double(&evalf_prec("log(2)", 53, true).unwrap()).unwrap(), 2f64.ln());
+    }
+
+    #[wasm_bindgen_test]
+    fn mixed_partial_derivatives() {
+        let e = "x**2*y**3*z";
+        assert_eq!(differentiate_multi(e, r#"{"x": 2, "y": 1}"#).unwrap(), "6*y**2*z");
+        assert_expr(&differentiate_multi(e, r#"{"y": 1, "x": 2}"#).unwrap(), "6*y**2*z");
+        assert_eq!(differentiate_multi(e, r#"{"x": 1, "y": 3, "z": 1}"#).unwrap(), "12*x");
+        assert_eq!(differentiate_multi(e, "{}").unwrap(), parse_expr(e, false).unwrap());
+        assert_eq!(err_msg(differentiate_multi(e, r#"{"2*x": 1}"#)), "'2*x' is not a symbol");
+        assert_eq!(
+            err_msg(differentiate_multi(e, r#"{"x": -1}"#)),
+            "order for 'x' must be a non-negative integer"
+        );
+        assert!(differentiate_multi(e, "[1]").is_err());
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn newton_root_finding() {
+        assert_close(newton_solve("x - cos(x)", "x", 1.0, 1e-14, 50).unwrap(), 0.7390851332151607);
+        assert_close(newton_solve("t**2 - 2", "t", 1.0, 1e-14, 50).unwrap(), std::f64::consts::SQRT_2);
+        assert_close(newton_solve("exp(x) - 3", "x", 0.0, 1e-14, 50).unwrap(), 3f64.ln());
+        assert_eq!(
+            err_msg(newton_solve("x**2 + 1", "x", 0.0, 1e-14, 50)),
+            "derivative is zero after 0 iterations"
+        );
+        assert_eq!(
+            err_msg(newton_solve("x**2 + 1", "x", 0.5, 1e-14, 20)),
+            "no convergence after 20 iterations"
+        );
+        assert_eq!(
+            err_msg(newton_solve("x*y - 1", "x", 1.0, 1e-14, 20)),
+            "free symbols besides the variable: y"
+        );
+        assert_eq!(
+            err_msg(newton_solve("x", "", 1.0, 1e-14, 20)),
+            r#"invalid symbol name "": empty expression"#
+        );
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn polynomial_degree_and_coefficients() {
+        assert_eq!(poly_degree("(x - 1)**2*(x + 3)", "x").unwrap(), 3);
+        assert_eq!(json_list(&poly_coeffs("(x - 1)**2*(x + 3)", "x").unwrap()), ["3", "-5", "1", "1"]);
+        assert_eq!(json_list(&poly_coeffs("k*t**2 + 4", "t").unwrap()), ["4", "0", "k"]);
+        assert_eq!(poly_degree("5", "x").unwrap(), 0);
+        assert_eq!(err_msg(poly_degree("sin(x)", "x")), "'sin(x)' is not a polynomial in x");
+        assert_eq!(err_msg(poly_coeffs("1/x", "x")), "'1/x' is not a polynomial in x");
+        assert!(poly_degree("x", "").is_err());
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn evaluation_at_a_point() {
+        let v = eval_at("x**2*y + sin(x)", r#"{"x": 1.5, "y": -2}"#).unwrap();
+        assert_close(v, 1.5 * 1.5 * -2.0 + 1.5f64.sin());
+        assert_eq!(eval_at("x + y", r#"{"x": 1, "y": 2, "z": 3}"#).unwrap(), 3.0);
+        assert_eq!(eval_at("pi", "{}").unwrap(), std::f64::consts::PI);
+        assert_eq!(err_msg(eval_at("x + y", r#"{"x": 1}"#)), "no value given for y");
+        assert!(err_msg(eval_at("x", r#"{"x": "1"}"#)).starts_with("expected a JSON object of numbers"));
+        assert!(err_msg(eval_at("sqrt(x)", r#"{"x": -1}"#)).starts_with("cannot evaluate"));
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn csv_matrix_shape_is_checked() {
+        assert_eq!(err_msg(matrix_det(2, 2, "1, 2, 3")), "expected 4 elements for a 2x2 matrix, got 3");
+        assert!(matrix_det(65536, 65536, "1").is_err());
+        assert_still_works();
+    }
+
+    #[wasm_bindgen_test]
+    fn csv_matrix_output_is_unaligned() {
+        assert_eq!(matrix_transpose(2, 2, "1, 2, 30, y + 1").unwrap().trim_end(), "[1, 30]\n[2, 1 + y]");
+        assert_eq!(matrix_mul(1, 2, "1, 2", 2, 1, "10, x").unwrap().trim_end(), "[10 + 2*x]");
+        assert_eq!(matrix_inv(2, 2, "2, 0, 0, 4").unwrap().trim_end(), "[1/2, 0]\n[0, 1/4]");
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_shape_errors_leave_the_module_usable() {
+        let a = r#"[["1", "2", "3"], ["4", "5", "6"]]"#;
+        let b = r#"[["1", "2"], ["3", "4"]]"#;
+        let cases = [
+            (matrix_mul_json(a, b), "cannot multiply matrices: incompatible shapes 2x3 and 2x2"),
+            (matrix_add(a, b), "cannot add matrices: incompatible shapes 2x3 and 2x2"),
+            (matrix_sub(b, a), "cannot subtract matrices: incompatible shapes 2x2 and 2x3"),
+            (matrix_inv_json(a), "cannot invert matrix: expected a square matrix, got 2x3"),
+            (matrix_det_json(a), "cannot take determinant: expected a square matrix, got 2x3"),
+            (matrix_inv_json(r#"[["1", "2"], ["2", "4"]]"#), "cannot invert matrix: matrix is singular"),
+            (matrix_mul(2, 3, "1, 2, 3, 4, 5, 6", 2, 2, "1, 2, 3, 4"), "cannot multiply matrices: incompatible shapes 2x3 and 2x2"),
+            (matrix_inv(1, 2, "1, 2"), "cannot invert matrix: expected a square matrix, got 1x2"),
+            (matrix_det(1, 2, "1, 2"), "cannot take determinant: expected a square matrix, got 1x2"),
+        ];
+        for (result, expected) in cases {
+            assert_eq!(err_msg(result), expected);
+            assert_still_works();
+            assert_eq!(matrix_det_json(b).unwrap(), "-2");
+        }
+        assert_eq!(json_matrix(&matrix_transpose_json(a).unwrap()), [["1", "4"], ["2", "5"], ["3", "6"]]);
+    }
+
+    #[wasm_bindgen_test]
+    fn sparse_triplets() {
+        let t = r#"[[1, 2, "y"], [0, 0, "x"], [1, 2, "y"], [0, 1, "0"]]"#;
+        let out: Vec<(u32, u32, String)> = serde_json::from_str(&sparse_from_triplets(t, 2, 3).unwrap()).unwrap();
+        assert_eq!(out, [(0, 0, "x".to_string()), (1, 2, "2*y".to_string())]);
+        assert_matrix(&sparse_to_dense(t, 2, 3).unwrap(), &[&["x", "0", "0"], &["0", "0", "2*y"]]);
+        assert_eq!(err_msg(sparse_to_dense(t, 2, 2)), "index 2 out of range for length 2");
+        assert_eq!(err_msg(sparse_from_triplets(r#"[[5, 0, "1"]]"#, 2, 2)), "index 5 out of range for length 2");
+        assert!(err_msg(sparse_from_triplets(r#"[[0, 0]]"#, 2, 2)).starts_with("expected a JSON array of [row, col, value] triplets"));
+        assert!(sparse_from_triplets(r#"[[-1, 0, "1"]]"#, 2, 2).is_err());
+        assert_eq!(sparse_from_triplets("[]", 3, 3).unwrap(), "[]");
+        assert_still_works();
+    }
 }
@@ -6,15 +6,394 @@
 
 use crate::symengine_ffi::*;
 use std::ffi::{CStr, CString};
-use std::os::raw::c_int;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::os::raw::{c_int, c_long};
+
+/// Error returned when an expression string cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input is empty or only whitespace.
+    Empty,
+    /// The input contains a NUL byte at this byte offset, which cannot be
+    /// passed to C.
+    NulByte { position: usize },
+    /// SymEngine rejected the input. The cwrapper reports only success or
+    /// failure, so there is no position information.
+    Syntax { input: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty expression"),
+            ParseError::NulByte { position } => {
+                write!(f, "expression contains a NUL byte at offset {}", position)
+            }
+            ParseError::Syntax { input } => write!(f, "could not parse expression {:?}", input),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error class reported by a SymEngine call, mirroring the cwrapper's
+/// `CWRAPPER_OUTPUT_TYPE` status codes.
+///
+/// In the `-fno-exceptions` wasm build SymEngine aborts instead of returning
+/// a nonzero status, so the `try_*` methods also check the conditions they
+/// know to be fatal before making the FFI call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymEngineError {
+    RuntimeError,
+    DivByZero,
+    NotImplemented,
+    DomainError,
+    ParseError,
+    /// A numeric result was requested but free symbols remain.
+    SymbolicResult,
+}
+
+impl SymEngineError {
+    /// Map a cwrapper status code to an error; 0 means success. Unknown
+    /// codes are reported as `RuntimeError`.
+    pub fn from_code(code: c_int) -> Option<Self> {
+        match code {
+            0 => None,
+            2 => Some(SymEngineError::DivByZero),
+            3 => Some(SymEngineError::NotImplemented),
+            4 => Some(SymEngineError::DomainError),
+            5 => Some(SymEngineError::ParseError),
+            _ => Some(SymEngineError::RuntimeError),
+        }
+    }
+}
+
+impl fmt::Display for SymEngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SymEngineError::RuntimeError => "SymEngine runtime error",
+            SymEngineError::DivByZero => "division by zero",
+            SymEngineError::NotImplemented => "not implemented",
+            SymEngineError::DomainError => "domain error",
+            SymEngineError::ParseError => "parse error",
+            SymEngineError::SymbolicResult => "result still contains free symbols",
+        })
+    }
+}
+
+impl std::error::Error for SymEngineError {}
+
+impl From<ParseError> for SymEngineError {
+    fn from(_: ParseError) -> Self {
+        SymEngineError::ParseError
+    }
+}
+
+/// Turn a cwrapper status code into a `Result`.
+fn check(code: c_int) -> Result<(), SymEngineError> {
+    match SymEngineError::from_code(code) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Error returned by `Expr::symbols` for a bad list of names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolsError {
+    /// The list contains no names.
+    Empty,
+    /// A name is not an identifier (letters, digits and `_`, not starting
+    /// with a digit).
+    InvalidName(String),
+    /// A range like `x0:5` is malformed or empty.
+    InvalidRange(String),
+    /// The same name appears twice.
+    Duplicate(String),
+}
+
+impl fmt::Display for SymbolsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolsError::Empty => write!(f, "no symbol names given"),
+            SymbolsError::InvalidName(s) => write!(f, "invalid symbol name {:?}", s),
+            SymbolsError::InvalidRange(s) => write!(f, "invalid symbol range {:?}", s),
+            SymbolsError::Duplicate(s) => write!(f, "duplicate symbol name {:?}", s),
+        }
+    }
+}
+
+impl std::error::Error for SymbolsError {}
+
+/// Checks shared by the parse functions, returning the C string to parse.
+/// Syntax errors are caught here as well, since in the `-fno-exceptions`
+/// build SymEngine's parser aborts on them; builds with exceptions also
+/// report them through the `basic_parse` status, which is still checked.
+fn parse_input(s: &str) -> Result<CString, ParseError> {
+    if s.trim().is_empty() {
+        return Err(ParseError::Empty);
+    }
+    if let Some(position) = s.find('\0') {
+        return Err(ParseError::NulByte { position });
+    }
+    if !is_well_formed(s) {
+        return Err(ParseError::Syntax { input: s.to_string() });
+    }
+    Ok(CString::new(s).expect("NUL bytes checked above"))
+}
+
+/// Whether `s` fits SymEngine's expression grammar: numbers (including
+/// implicit products like `2x`), identifiers, calls `f(a, b)`, parentheses,
+/// prefix `-` and `~`, and the binary operators `+ - * / ** ^ & |` and
+/// `< > <= >= ==`. The `-fno-exceptions` build aborts on anything its parser
+/// rejects, so this errs on the side of refusing input.
+fn is_well_formed(s: &str) -> bool {
+    let b = s.as_bytes();
+    let ident = |c: u8| c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80;
+    let digits = |mut i: usize| {
+        while i < b.len() && b[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    // One entry per open parenthesis: whether it opened a call.
+    let mut open: Vec<bool> = Vec::new();
+    let mut want_operand = true;
+    let mut after_name = false;
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if want_operand {
+            match c {
+                b'-' | b'~' => i += 1,
+                b'(' => {
+                    open.push(false);
+                    i += 1;
+                }
+                b'0'..=b'9' | b'.' => {
+                    let int_end = digits(i);
+                    let frac_start = int_end + (b.get(int_end) == Some(&b'.')) as usize;
+                    let end = digits(frac_start);
+                    if end == frac_start && int_end == i {
+                        return false;
+                    }
+                    i = end;
+                    if matches!(b.get(i), Some(b'e' | b'E')) {
+                        let j = i + 1 + matches!(b.get(i + 1), Some(b'+' | b'-')) as usize;
+                        if b.get(j).is_some_and(u8::is_ascii_digit) {
+                            i = digits(j);
+                        }
+                    }
+                    // Implicit multiplication: `2x`, `1e5y`.
+                    while i < b.len() && ident(b[i]) {
+                        i += 1;
+                    }
+                    want_operand = false;
+                    after_name = false;
+                }
+                c if ident(c) => {
+                    while i < b.len() && ident(b[i]) {
+                        i += 1;
+                    }
+                    want_operand = false;
+                    after_name = true;
+                }
+                _ => return false,
+            }
+        } else {
+            let pair = &b[i..b.len().min(i + 2)];
+            if matches!(pair, b"**" | b"<=" | b">=" | b"==") {
+                i += 2;
+                want_operand = true;
+            } else {
+                match c {
+                    b'+' | b'-' | b'*' | b'/' | b'^' | b'&' | b'|' | b'<' | b'>' => want_operand = true,
+                    b'(' if after_name => {
+                        open.push(true);
+                        want_operand = true;
+                    }
+                    b')' if open.pop().is_some() => {}
+                    b',' if open.last() == Some(&true) => want_operand = true,
+                    _ => return false,
+                }
+                i += 1;
+            }
+            after_name = false;
+        }
+    }
+    !want_operand && open.is_empty()
+}
+
+/// Whether `name` is usable as a symbol or function name: letters, digits
+/// and `_`, not starting with a digit.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Error returned by matrix operations whose inputs are unsuitable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatrixError {
+    /// The operation needs a square matrix; carries the actual shape.
+    NotSquare { rows: u32, cols: u32 },
+    /// The operands' shapes are incompatible; carries both shapes.
+    ShapeMismatch { left: (u32, u32), right: (u32, u32) },
+    /// The coefficient matrix is singular.
+    Singular,
+    /// A factorization without row swaps would divide by a zero pivot.
+    ZeroPivot,
+    /// The operation needs a symmetric matrix.
+    NotSymmetric,
+    /// A row or column index is past the end; carries the index and the
+    /// number of rows or columns.
+    OutOfRange { index: u32, len: u32 },
+    /// Two vectors have different lengths.
+    LengthMismatch { left: usize, right: usize },
+    /// A flat element list does not have rows × cols entries.
+    ElementCount { rows: u32, cols: u32, len: usize },
+    /// A row of nested input has a different length from the first row.
+    Ragged { row: usize, len: usize, expected: usize },
+    /// SymEngine itself reported a failure.
+    SymEngine(SymEngineError),
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatrixError::NotSquare { rows, cols } => {
+                write!(f, "expected a square matrix, got {}x{}", rows, cols)
+            }
+            MatrixError::ShapeMismatch { left, right } => write!(
+                f,
+                "incompatible shapes {}x{} and {}x{}",
+                left.0, left.1, right.0, right.1
+            ),
+            MatrixError::Singular => write!(f, "matrix is singular"),
+            MatrixError::ZeroPivot => {
+                write!(f, "factorization needs row swaps (zero pivot)")
+            }
+            MatrixError::NotSymmetric => write!(f, "matrix is not symmetric"),
+            MatrixError::OutOfRange { index, len } => {
+                write!(f, "index {} out of range for length {}", index, len)
+            }
+            MatrixError::LengthMismatch { left, right } => {
+                write!(f, "vectors have different lengths {} and {}", left, right)
+            }
+            MatrixError::ElementCount { rows, cols, len } => write!(
+                f,
+                "expected {} elements for a {}x{} matrix, got {}",
+                u64::from(*rows) * u64::from(*cols),
+                rows,
+                cols,
+                len
+            ),
+            MatrixError::Ragged { row, len, expected } => write!(
+                f,
+                "ragged matrix: row {} has {} entries, expected {}",
+                row, len, expected
+            ),
+            MatrixError::SymEngine(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+impl From<SymEngineError> for MatrixError {
+    fn from(e: SymEngineError) -> Self {
+        MatrixError::SymEngine(e)
+    }
+}
+
+/// Error returned by `newton_solve`. `iterations` is the number of Newton
+/// steps completed before the failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// The variable to solve for is not a symbol.
+    NotSymbol,
+    /// The expression has free symbols besides the variable; carries their
+    /// names.
+    FreeSymbols(Vec<String>),
+    /// The derivative vanished, so no Newton step can be taken.
+    ZeroDerivative { iterations: u32 },
+    /// The function or its derivative is not a real number at the current
+    /// iterate, e.g. `sqrt(x)` once `x` drifts negative.
+    NotReal { iterations: u32 },
+    /// No step fell below the tolerance within the iteration limit, or the
+    /// iterate overflowed.
+    NoConvergence { iterations: u32 },
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NotSymbol => write!(f, "can only solve for a symbol"),
+            SolveError::FreeSymbols(names) => {
+                write!(f, "free symbols besides the variable: {}", names.join(", "))
+            }
+            SolveError::ZeroDerivative { iterations } => {
+                write!(f, "derivative is zero after {} iterations", iterations)
+            }
+            SolveError::NotReal { iterations } => {
+                write!(f, "value is not real after {} iterations", iterations)
+            }
+            SolveError::NoConvergence { iterations } => {
+                write!(f, "no convergence after {} iterations", iterations)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// Common kinds of expression node, as returned by `Expr::type_kind`.
+/// `E**x` is a `Pow` with base `E`; there is no separate exp node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeKind {
+    Integer,
+    Rational,
+    Complex,
+    RealDouble,
+    ComplexDouble,
+    Constant,
+    Infinity,
+    NaN,
+    Symbol,
+    Add,
+    Mul,
+    Pow,
+    FunctionSymbol,
+    Derivative,
+    Sin,
+    Cos,
+    Tan,
+    Log,
+    Abs,
+    /// Any other class, with its `type_code`.
+    Other(i32),
+}
 
 /// A symbolic expression backed by SymEngine.
 pub struct Expr {
     ptr: *mut BasicStruct,
 }
 
+// Without thread-safe RCP this is only sound while the receiving thread is
+// the only one using SymEngine: unrelated expressions still share cached
+// nodes such as small integers, whose counts are not atomic.
 unsafe impl Send for Expr {}
 
+/// Sharing `&Expr` between threads means concurrent clones and drops of the
+/// same node, which needs SymEngine's atomic RCP counts. build.rs checks
+/// that SymEngine was configured with `WITH_SYMENGINE_THREAD_SAFE`.
+#[cfg(feature = "thread-safe")]
+unsafe impl Sync for Expr {}
+
 // ---------------------------------------------------------------------------
 // Helper: call an FFI function that takes (*mut result, *const a) → c_int
 // ---------------------------------------------------------------------------
@@ -74,15 +453,38 @@ impl Expr {
 
     /// Parse a mathematical expression string (e.g. `"x**2 + 2*x + 1"`).
     pub fn parse(s: &str) -> Self {
+        Self::try_parse(s).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Parse a string expression, reporting bad input instead of panicking.
+    /// Empty input, NUL bytes and syntax errors are rejected before reaching
+    /// SymEngine.
+    pub fn try_parse(s: &str) -> Result<Self, ParseError> {
+        let c_str = parse_input(s)?;
         unsafe {
-            let ptr = basic_new_heap();
-            let c_str = CString::new(s).expect("expression contains null byte");
-            basic_parse(ptr, c_str.as_ptr());
-            Self { ptr }
+            let e = Self { ptr: basic_new_heap() };
+            if basic_parse(e.ptr, c_str.as_ptr()) != 0 {
+                return Err(ParseError::Syntax { input: s.to_string() });
+            }
+            Ok(e)
         }
     }
 
-    /// Create a symbolic variable.
+    /// Parse with explicit parser options. With `convert_xor`, `^` is read as
+    /// exponentiation (as in `x^2`), which is also what `parse` does.
+    pub fn parse_with_options(s: &str, convert_xor: bool) -> Result<Self, ParseError> {
+        let c_str = parse_input(s)?;
+        unsafe {
+            let e = Self { ptr: basic_new_heap() };
+            if basic_parse2(e.ptr, c_str.as_ptr(), convert_xor as c_int) != 0 {
+                return Err(ParseError::Syntax { input: s.to_string() });
+            }
+            Ok(e)
+        }
+    }
+
+    /// Create a symbolic variable. Panics if `name` contains a NUL byte; see
+    /// `try_symbol`.
     pub fn symbol(name: &str) -> Self {
         unsafe {
             let ptr = basic_new_heap();
@@ -92,6 +494,96 @@ impl Expr {
         }
     }
 
+    /// Like `symbol`, but an empty (or all-whitespace) name or one with a
+    /// NUL byte is an error instead of a panic or a nameless symbol.
+    pub fn try_symbol(name: &str) -> Result<Self, ParseError> {
+        if name.trim().is_empty() {
+            return Err(ParseError::Empty);
+        }
+        if let Some(position) = name.find('\0') {
+            return Err(ParseError::NulByte { position });
+        }
+        Ok(Self::symbol(name))
+    }
+
+    /// Create several symbols from names separated by whitespace and/or
+    /// commas, like SymPy's `symbols("x y z")`. A range `x0:5` expands to
+    /// `x0, x1, x2, x3, x4`, and `x:3` to `x0, x1, x2`.
+    pub fn symbols(names: &str) -> Result<Vec<Self>, SymbolsError> {
+        let mut expanded: Vec<String> = Vec::new();
+        for token in names.split(|c: char| c == ',' || c.is_whitespace()) {
+            if token.is_empty() {
+                continue;
+            }
+            match token.split_once(':') {
+                Some((head, end)) => {
+                    let bad = || SymbolsError::InvalidRange(token.to_string());
+                    let prefix = head.trim_end_matches(|c: char| c.is_ascii_digit());
+                    let start = match &head[prefix.len()..] {
+                        "" => 0,
+                        digits => digits.parse::<u32>().map_err(|_| bad())?,
+                    };
+                    let end: u32 = end.parse().map_err(|_| bad())?;
+                    if start >= end {
+                        return Err(bad());
+                    }
+                    expanded.extend((start..end).map(|i| format!("{}{}", prefix, i)));
+                }
+                None => expanded.push(token.to_string()),
+            }
+        }
+        if expanded.is_empty() {
+            return Err(SymbolsError::Empty);
+        }
+        let mut seen = std::collections::HashSet::new();
+        for name in &expanded {
+            if !is_identifier(name) {
+                return Err(SymbolsError::InvalidName(name.clone()));
+            }
+            if !seen.insert(name.as_str()) {
+                return Err(SymbolsError::Duplicate(name.clone()));
+            }
+        }
+        Ok(expanded.iter().map(|n| Self::symbol(n)).collect())
+    }
+
+    /// Undefined function applied to `args`, e.g. `f(x, y)`. Differentiating
+    /// it gives an unevaluated Derivative.
+    ///
+    /// Panics if `name` is not an identifier; see `try_function`.
+    pub fn function(name: &str, args: &[Expr]) -> Self {
+        Self::try_function(name, args)
+            .unwrap_or_else(|_| panic!("invalid function name {:?}", name))
+    }
+
+    /// Undefined function applied to `args`, rejecting a `name` that is not
+    /// an identifier with `SymbolsError::InvalidName`.
+    pub fn try_function(name: &str, args: &[Expr]) -> Result<Self, SymbolsError> {
+        if !is_identifier(name) {
+            return Err(SymbolsError::InvalidName(name.to_string()));
+        }
+        let c_name = CString::new(name).unwrap();
+        let vec = VecExpr::from(args);
+        unsafe {
+            let r = Self { ptr: basic_new_heap() };
+            function_symbol_set(r.ptr, c_name.as_ptr(), vec.ptr);
+            Ok(r)
+        }
+    }
+
+    /// Name of an undefined function such as `f(x)`; `None` for anything else.
+    pub fn function_name(&self) -> Option<String> {
+        if self.type_name() != "FunctionSymbol" {
+            return None;
+        }
+        unsafe {
+            let s = function_symbol_get_name(self.ptr);
+            let result = CStr::from_ptr(s).to_string_lossy().into_owned();
+            basic_str_free(s);
+            Some(result)
+        }
+    }
+
     /// Create an integer from i32.
     pub fn integer(i: i32) -> Self {
         unsafe {
@@ -101,6 +593,37 @@ impl Expr {
         }
     }
 
+    /// Create an integer from i64 exactly. c_long is 32 bits on wasm32, so
+    /// values outside its range go through the decimal string.
+    pub fn integer_i64(i: i64) -> Self {
+        match c_long::try_from(i) {
+            Ok(l) => unsafe {
+                let ptr = basic_new_heap();
+                integer_set_si(ptr, l);
+                Self { ptr }
+            },
+            Err(_) => Self::integer_from_str(&i.to_string()),
+        }
+    }
+
+    /// Create an integer from u64 exactly; see `integer_i64`.
+    pub fn integer_u64(u: u64) -> Self {
+        match i64::try_from(u) {
+            Ok(i) => Self::integer_i64(i),
+            Err(_) => Self::integer_from_str(&u.to_string()),
+        }
+    }
+
+    /// Create an integer from a decimal string (optional leading `-`, then
+    /// digits), rejecting anything else before it reaches SymEngine.
+    pub fn try_integer_from_str(s: &str) -> Result<Self, ParseError> {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseError::Syntax { input: s.to_string() });
+        }
+        Ok(Self::integer_from_str(s))
+    }
+
     /// Create an integer from a decimal string.
     pub fn integer_from_str(s: &str) -> Self {
         unsafe {
@@ -120,6 +643,17 @@ impl Expr {
         }
     }
 
+    /// Create `re + im*I`. Either part may be symbolic; a zero part
+    /// collapses, so `complex(3, 0)` is just `3`.
+    pub fn complex(re: &Expr, im: &Expr) -> Self {
+        re.add(&im.mul(&Self::imaginary_unit()))
+    }
+
+    /// Create a complex double `re + im*I`.
+    pub fn complex_double(re: f64, im: f64) -> Self {
+        Self::complex(&Self::real_double(re), &Self::real_double(im))
+    }
+
     /// Create a real double.
     pub fn real_double(d: f64) -> Self {
         unsafe {
@@ -171,6 +705,15 @@ impl Expr {
     unary_op!(sec, basic_sec);
     unary_op!(cot, basic_cot);
 
+    /// Two-argument arctangent `atan2(self, x)`, with `self` as the y coordinate.
+    pub fn atan2(&self, x: &Expr) -> Self {
+        unsafe {
+            let r = basic_new_heap();
+            basic_atan2(r, self.ptr, x.ptr);
+            Self { ptr: r }
+        }
+    }
+
     // =====================================================================
     // Hyperbolic
     // =====================================================================
@@ -200,6 +743,18 @@ impl Expr {
     unary_op!(erfc, basic_erfc);
     unary_op!(lambertw, basic_lambertw);
     binary_op!(beta, basic_beta);
+    binary_op!(lowergamma, basic_lowergamma);
+    binary_op!(uppergamma, basic_uppergamma);
+    binary_op!(kronecker_delta, basic_kronecker_delta);
+
+    /// Polygamma function of order `n` evaluated at `self`: `polygamma(n, self)`.
+    pub fn polygamma(&self, n: &Expr) -> Self {
+        unsafe {
+            let r = basic_new_heap();
+            basic_polygamma(r, n.ptr, self.ptr);
+            Self { ptr: r }
+        }
+    }
 
     // =====================================================================
     // Rounding / sign
@@ -219,6 +774,136 @@ impl Expr {
         }
     }
 
+    /// Like `diff`, but reports a non-symbol `sym` as `RuntimeError` (the
+    /// status the cwrapper would return) instead of aborting.
+    pub fn try_diff(&self, sym: &Expr) -> Result<Self, SymEngineError> {
+        if !sym.is_symbol() {
+            return Err(SymEngineError::RuntimeError);
+        }
+        unsafe {
+            let r = Self { ptr: basic_new_heap() };
+            check(basic_diff(r.ptr, self.ptr, sym.ptr))?;
+            Ok(r)
+        }
+    }
+
+    /// Partial derivatives with respect to each of `syms`, in order.
+    pub fn gradient(&self, syms: &[Expr]) -> Vec<Expr> {
+        syms.iter().map(|s| self.diff(s)).collect()
+    }
+
+    /// `n`-th derivative with respect to `sym`; `n = 0` returns a copy.
+    pub fn diff_n(&self, sym: &Expr, n: u32) -> Self {
+        let mut r = self.clone();
+        for _ in 0..n {
+            r = r.diff(sym);
+        }
+        r
+    }
+
+    /// Mixed partial derivative, e.g. `[(&x, 2), (&y, 1)]` for ∂³/∂x²∂y.
+    /// Every variable is checked before differentiating, so a non-symbol
+    /// fails without doing any work.
+    pub fn diff_multi(&self, syms: &[(&Expr, u32)]) -> Result<Self, SymEngineError> {
+        if syms.iter().any(|(s, _)| !s.is_symbol()) {
+            return Err(SymEngineError::RuntimeError);
+        }
+        let mut r = self.clone();
+        for &(sym, n) in syms {
+            for _ in 0..n {
+                r = r.try_diff(sym)?;
+            }
+        }
+        Ok(r)
+    }
+
+    /// Truncated Taylor polynomial of degree `order` around `sym = about`,
+    /// expanded so coefficients are collected. `about` may be symbolic.
+    /// Panics if `self` has a pole at `about`; see `try_taylor`.
+    pub fn taylor(&self, sym: &Expr, about: &Expr, order: u32) -> Self {
+        self.try_taylor(sym, about, order)
+            .unwrap_or_else(|e| panic!("taylor: {} about {} = {}: {}", self, sym, about, e))
+    }
+
+    /// Like `taylor`, but fallible. A removable singularity at `about`
+    /// (e.g. `sin(x)/x` at 0) is handled by expanding the numerator and
+    /// denominator separately and dividing the series. A pole, or a
+    /// singularity that isn't a quotient, is a `DomainError`; a non-symbol
+    /// `sym` is a `RuntimeError`.
+    pub fn try_taylor(&self, sym: &Expr, about: &Expr, order: u32) -> Result<Self, SymEngineError> {
+        if !sym.is_symbol() {
+            return Err(SymEngineError::RuntimeError);
+        }
+        let coeffs = match self.taylor_coeffs(sym, about, order as usize + 1) {
+            Ok(coeffs) => coeffs,
+            Err(_) => self.quotient_taylor_coeffs(sym, about, order as usize + 1)?,
+        };
+        let dx = sym.sub(about);
+        let mut sum = Expr::zero();
+        for (k, c) in coeffs.iter().enumerate() {
+            sum = sum.add(&c.mul(&dx.pow(&Expr::integer(k as i32))));
+        }
+        Ok(sum.expand())
+    }
+
+    /// The first `n` Taylor coefficients `f⁽ᵏ⁾(about) / k!`, by substitution.
+    /// An undefined (nan or zoo) value is a `DomainError`.
+    fn taylor_coeffs(&self, sym: &Expr, about: &Expr, n: usize) -> Result<Vec<Expr>, SymEngineError> {
+        let mut d = self.clone();
+        let mut coeffs = Vec::with_capacity(n);
+        for k in 0..n {
+            let c = d.subs(sym, about).expand();
+            if c.is_undefined() {
+                return Err(SymEngineError::DomainError);
+            }
+            coeffs.push(c.div(&factorial(k as u32)));
+            if k + 1 < n {
+                d = d.diff(sym);
+            }
+        }
+        Ok(coeffs)
+    }
+
+    /// Taylor coefficients of `numer / denom` as a power series quotient.
+    /// If the denominator has a zero of order m at `about`, the numerator
+    /// must too (else it is a pole); both series are shifted down by m and
+    /// divided term by term.
+    fn quotient_taylor_coeffs(
+        &self, sym: &Expr, about: &Expr, n: usize,
+    ) -> Result<Vec<Expr>, SymEngineError> {
+        // Deeper zeros than this are treated as unresolvable.
+        const MAX_ZERO_ORDER: usize = 32;
+        let (numer, denom) = self.numer_denom();
+        let mut d = denom.clone();
+        let mut m = 0;
+        loop {
+            let v = d.subs(sym, about).expand();
+            if v.is_undefined() || m > MAX_ZERO_ORDER {
+                return Err(SymEngineError::DomainError);
+            }
+            if !v.is_zero() {
+                break;
+            }
+            d = d.diff(sym);
+            m += 1;
+        }
+        let a = numer.taylor_coeffs(sym, about, m + n)?;
+        let b = denom.taylor_coeffs(sym, about, m + n)?;
+        if a[..m].iter().any(|c| !c.is_zero()) {
+            return Err(SymEngineError::DomainError);
+        }
+        let (a, b) = (&a[m..], &b[m..]);
+        let mut c: Vec<Expr> = Vec::with_capacity(n);
+        for i in 0..n {
+            let mut t = a[i].clone();
+            for j in 1..=i {
+                t = t.sub(&b[j].mul(&c[i - j]));
+            }
+            c.push(t.div(&b[0]).expand());
+        }
+        Ok(c)
+    }
+
     // =====================================================================
     // Substitution & evaluation
     // =====================================================================
@@ -234,27 +919,166 @@ impl Expr {
 
     /// Multi-substitution: pairs is [(from1, to1), (from2, to2), ...].
     pub fn subs_map(&self, pairs: &[(&Expr, &Expr)]) -> Self {
+        let mut map = SubsMap::new();
+        for (k, v) in pairs {
+            map.insert(k, v);
+        }
+        self.subs_with(&map)
+    }
+
+    /// Simultaneous substitution with a prebuilt table, which can be reused
+    /// across many expressions.
+    pub fn subs_with(&self, map: &SubsMap) -> Self {
         unsafe {
-            let map = mapbasicbasic_new();
-            for (k, v) in pairs {
-                mapbasicbasic_insert(map, k.ptr, v.ptr);
-            }
             let r = basic_new_heap();
-            basic_subs(r, self.ptr, map);
-            mapbasicbasic_free(map);
+            basic_subs(r, self.ptr, map.ptr);
             Self { ptr: r }
         }
     }
 
-    /// Numerical evaluation to `bits` bits of precision.
-    pub fn evalf(&self, bits: u32) -> Self {
+    /// Substitute a `RealDouble` for each symbol, in one simultaneous pass.
+    pub fn subs_f64(&self, bindings: &[(&Expr, f64)]) -> Self {
+        let mut map = SubsMap::new();
+        for &(k, v) in bindings {
+            map.insert(k, &Expr::real_double(v));
+        }
+        self.subs_with(&map)
+    }
+
+    /// Substitute `bindings` and evaluate to a double. Fails with
+    /// `SymbolicResult` if a free symbol has no binding and `DomainError` if
+    /// the value is not real. nan, whether from a nan binding or an
+    /// undefined result, is returned as `f64::NAN`.
+    pub fn eval_at(&self, bindings: &[(&Expr, f64)]) -> Result<f64, SymEngineError> {
+        let r = self.subs_f64(bindings);
+        if !r.free_symbols().is_empty() {
+            return Err(SymEngineError::SymbolicResult);
+        }
+        if r.eq(&Self::nan()) {
+            return Ok(f64::NAN);
+        }
+        r.to_f64().ok_or(SymEngineError::DomainError)
+    }
+
+    /// Numerical evaluation to `bits` bits of precision. With `real_only`
+    /// the result is restricted to the real domain (non-real values become
+    /// nan); otherwise complex results are allowed.
+    pub fn evalf(&self, bits: u32, real_only: bool) -> Self {
         unsafe {
             let r = basic_new_heap();
-            basic_evalf(r, self.ptr, bits as _, 0);
+            basic_evalf(r, self.ptr, bits as _, real_only as c_int);
             Self { ptr: r }
         }
     }
 
+    // =====================================================================
+    // Fallible variants
+    // =====================================================================
+
+    /// Division that reports division by an exact zero instead of
+    /// producing `zoo` or aborting.
+    pub fn try_div(&self, other: &Expr) -> Result<Self, SymEngineError> {
+        if other.is_zero() {
+            return Err(SymEngineError::DivByZero);
+        }
+        unsafe {
+            let r = Self { ptr: basic_new_heap() };
+            check(basic_div(r.ptr, self.ptr, other.ptr))?;
+            Ok(r)
+        }
+    }
+
+    /// Power that reports zero raised to a negative exponent.
+    pub fn try_pow(&self, exp: &Expr) -> Result<Self, SymEngineError> {
+        if self.is_zero() && exp.is_negative() {
+            return Err(SymEngineError::DivByZero);
+        }
+        unsafe {
+            let r = Self { ptr: basic_new_heap() };
+            check(basic_pow(r.ptr, self.ptr, exp.ptr))?;
+            Ok(r)
+        }
+    }
+
+    /// Numeric evaluation. Without MPFR only double precision is available,
+    /// so more than 53 bits is `NotImplemented`. A `nan` or complex-infinity
+    /// result (e.g. `log(0)`, `gamma(-3)`, `zeta(1)`) is a `DomainError`, and
+    /// leftover free symbols are `SymbolicResult`.
+    pub fn try_evalf(&self, bits: u32, real_only: bool) -> Result<Self, SymEngineError> {
+        if bits > 53 {
+            return Err(SymEngineError::NotImplemented);
+        }
+        let r = unsafe {
+            let r = Self { ptr: basic_new_heap() };
+            check(basic_evalf(r.ptr, self.ptr, bits as _, real_only as c_int))?;
+            r
+        };
+        if !r.free_symbols().is_empty() {
+            return Err(SymEngineError::SymbolicResult);
+        }
+        if r.is_undefined() {
+            return Err(SymEngineError::DomainError);
+        }
+        Ok(r)
+    }
+
+    /// Whether the expression is `nan` or complex infinity (`zoo`), the
+    /// values SymEngine gives for undefined results such as `0/0` or `1/0`.
+    fn is_undefined(&self) -> bool {
+        self.eq(&Self::nan()) || self.eq(&Self::complex_infinity())
+    }
+
+    /// Evaluate to a machine double. Returns `None` if the expression has
+    /// free symbols or its value is not real.
+    pub fn to_f64(&self) -> Option<f64> {
+        if !self.free_symbols().is_empty() {
+            return None;
+        }
+        unsafe {
+            if is_a_RealDouble(self.ptr) != 0 {
+                return Some(real_double_get_d(self.ptr));
+            }
+            let v = self.evalf(53, false);
+            if is_a_RealDouble(v.ptr) != 0 {
+                Some(real_double_get_d(v.ptr))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// The value of an exact integer, if it fits in i64. Rationals, floats
+    /// and symbolic expressions give `None`.
+    pub fn to_i64(&self) -> Option<i64> {
+        if !self.is_integer() {
+            return None;
+        }
+        // Not integer_get_si: its c_long is 32 bits on wasm32, and an
+        // out-of-range value may abort. The decimal form is exact.
+        self.to_string().parse().ok()
+    }
+
+    /// Evaluate to a complex double `(re, im)`; real values have `im == 0`.
+    /// Returns `None` if the expression has free symbols or is not numeric.
+    pub fn as_real_imag(&self) -> Option<(f64, f64)> {
+        if !self.free_symbols().is_empty() {
+            return None;
+        }
+        let v = self.evalf(53, false);
+        unsafe {
+            if is_a_RealDouble(v.ptr) != 0 {
+                Some((real_double_get_d(v.ptr), 0.0))
+            } else if is_a_ComplexDouble(v.ptr) != 0 {
+                Some((
+                    complex_double_real_part(v.ptr),
+                    complex_double_imaginary_part(v.ptr),
+                ))
+            } else {
+                None
+            }
+        }
+    }
+
     // =====================================================================
     // Comparison & type checking
     // =====================================================================
@@ -267,17 +1091,24 @@ impl Expr {
         unsafe { basic_neq(self.ptr, other.ptr) != 0 }
     }
 
+    /// SymEngine's structural hash; equal expressions hash equally.
+    pub fn hash_value(&self) -> u64 {
+        unsafe { basic_hash(self.ptr) as u64 }
+    }
+
+    // The number_is_* predicates are only defined for numbers, so they
+    // report false for anything else instead of reaching SymEngine.
     pub fn is_zero(&self) -> bool {
-        unsafe { number_is_zero(self.ptr) != 0 }
+        self.is_number() && unsafe { number_is_zero(self.ptr) != 0 }
     }
     pub fn is_negative(&self) -> bool {
-        unsafe { number_is_negative(self.ptr) != 0 }
+        self.is_number() && unsafe { number_is_negative(self.ptr) != 0 }
     }
     pub fn is_positive(&self) -> bool {
-        unsafe { number_is_positive(self.ptr) != 0 }
+        self.is_number() && unsafe { number_is_positive(self.ptr) != 0 }
     }
     pub fn is_complex(&self) -> bool {
-        unsafe { number_is_complex(self.ptr) != 0 }
+        self.is_number() && unsafe { number_is_complex(self.ptr) != 0 }
     }
     pub fn has_symbol(&self, sym: &Expr) -> bool {
         unsafe { basic_has_symbol(self.ptr, sym.ptr) != 0 }
@@ -294,6 +1125,88 @@ impl Expr {
     pub fn is_symbol(&self) -> bool {
         unsafe { is_a_Symbol(self.ptr) != 0 }
     }
+    /// An exact complex number such as `3 + 4*I` (not a ComplexDouble).
+    pub fn is_complex_type(&self) -> bool {
+        unsafe { is_a_Complex(self.ptr) != 0 }
+    }
+    pub fn is_real_double(&self) -> bool {
+        unsafe { is_a_RealDouble(self.ptr) != 0 }
+    }
+
+    // =====================================================================
+    // Structure
+    // =====================================================================
+
+    /// SymEngine's numeric type id of the outermost node. The numbering
+    /// depends on how SymEngine was built; prefer `type_kind` for matching.
+    pub fn type_code(&self) -> i32 {
+        unsafe { basic_get_type(self.ptr) as i32 }
+    }
+
+    /// Kind of the outermost node, decided by class name so it does not
+    /// depend on SymEngine's type numbering.
+    pub fn type_kind(&self) -> TypeKind {
+        match self.type_name().as_str() {
+            "Integer" => TypeKind::Integer,
+            "Rational" => TypeKind::Rational,
+            "Complex" => TypeKind::Complex,
+            "RealDouble" => TypeKind::RealDouble,
+            "ComplexDouble" => TypeKind::ComplexDouble,
+            "Constant" => TypeKind::Constant,
+            "Infty" => TypeKind::Infinity,
+            "NaN" => TypeKind::NaN,
+            "Symbol" => TypeKind::Symbol,
+            "Add" => TypeKind::Add,
+            "Mul" => TypeKind::Mul,
+            "Pow" => TypeKind::Pow,
+            "FunctionSymbol" => TypeKind::FunctionSymbol,
+            "Derivative" => TypeKind::Derivative,
+            "Sin" => TypeKind::Sin,
+            "Cos" => TypeKind::Cos,
+            "Tan" => TypeKind::Tan,
+            "Log" => TypeKind::Log,
+            "Abs" => TypeKind::Abs,
+            _ => TypeKind::Other(self.type_code()),
+        }
+    }
+
+    /// SymEngine class name of the outermost node, e.g. `"Add"`, `"Symbol"`.
+    pub fn type_name(&self) -> String {
+        unsafe {
+            let s = basic_get_class_from_id(basic_get_type(self.ptr));
+            let result = CStr::from_ptr(s).to_string_lossy().into_owned();
+            basic_str_free(s);
+            result
+        }
+    }
+
+    /// Top-level arguments (e.g. the terms of an Add). Atoms have none.
+    pub fn args(&self) -> Vec<Expr> {
+        self.args_vec().into()
+    }
+
+    /// Number of top-level arguments.
+    pub fn num_args(&self) -> usize {
+        self.args_vec().len()
+    }
+
+    fn args_vec(&self) -> VecExpr {
+        let vec = VecExpr::new();
+        unsafe { basic_get_args(self.ptr, vec.ptr) };
+        vec
+    }
+
+    /// Number of operation nodes in the expression tree. Atoms (symbols,
+    /// numbers, constants) count as 0 and every other node counts once
+    /// regardless of arity, so `x + y + z` is 1 and `sin(cos(tan(x)))` is 3.
+    pub fn count_ops(&self) -> usize {
+        let args = self.args();
+        if args.is_empty() {
+            0
+        } else {
+            1 + args.iter().map(Expr::count_ops).sum::<usize>()
+        }
+    }
 
     // =====================================================================
     // Algebraic
@@ -318,37 +1231,107 @@ impl Expr {
         }
     }
 
-    // =====================================================================
-    // Free symbols
-    // =====================================================================
-
-    /// Return the set of free symbols as a Vec<String>.
-    pub fn free_symbols(&self) -> Vec<String> {
-        unsafe {
-            let set = setbasic_new();
-            basic_free_symbols(self.ptr, set);
-            collect_set_strings(set)
+    /// Degree in `x` of the expanded expression, or `None` if it is not a
+    /// polynomial in `x` (e.g. `sin(x)`, `1/x`, `x**(1/2)`). Other symbols
+    /// are treated as constants; `0` has degree 0.
+    pub fn degree(&self, x: &Expr) -> Option<u32> {
+        let e = self.expand();
+        if e.type_kind() == TypeKind::Add {
+            e.args().iter().try_fold(0, |d, t| Some(d.max(t.term_degree(x)?)))
+        } else {
+            e.term_degree(x)
         }
     }
 
-    // =====================================================================
+    /// Degree in `x` of a single product term.
+    fn term_degree(&self, x: &Expr) -> Option<u32> {
+        if !self.has_symbol(x) {
+            return Some(0);
+        }
+        if self.eq(x) {
+            return Some(1);
+        }
+        match self.type_kind() {
+            TypeKind::Mul => self
+                .args()
+                .iter()
+                .try_fold(0u32, |d, f| d.checked_add(f.term_degree(x)?)),
+            TypeKind::Pow => {
+                let args = self.args();
+                if !args[0].eq(x) {
+                    return None;
+                }
+                args[1].to_i64().and_then(|n| u32::try_from(n).ok())
+            }
+            _ => None,
+        }
+    }
+
+    /// Coefficients in `x` in ascending powers, from `x**0` up to the
+    /// degree, or `None` if the expression is not a polynomial in `x`.
+    pub fn coeffs(&self, x: &Expr) -> Option<Vec<Expr>> {
+        let d = self.degree(x)?;
+        let e = self.expand();
+        Some((0..=d).map(|n| e.poly_coeff(x, n)).collect())
+    }
+
+    /// Coefficient of the highest power of `x`, or `None` if the expression
+    /// is not a polynomial in `x`.
+    pub fn leading_coeff(&self, x: &Expr) -> Option<Expr> {
+        let d = self.degree(x)?;
+        Some(self.expand().poly_coeff(x, d))
+    }
+
+    /// Coefficient of `x**n` in an expanded polynomial. The constant term is
+    /// taken by substituting `x = 0`, which collects every term free of `x`
+    /// without relying on how `coeff` treats `n = 0`.
+    fn poly_coeff(&self, x: &Expr, n: u32) -> Expr {
+        if n == 0 {
+            self.subs(x, &Expr::zero())
+        } else {
+            self.coeff(x, &Expr::from(n as i64))
+        }
+    }
+
+    // =====================================================================
+    // Free symbols
+    // =====================================================================
+
+    /// Return the set of free symbols as a Vec<String>.
+    pub fn free_symbols(&self) -> Vec<String> {
+        self.free_symbol_set().iter().map(|s| s.to_string()).collect()
+    }
+
+    /// The free symbols as expressions, ready to substitute into.
+    pub fn free_symbol_exprs(&self) -> Vec<Expr> {
+        self.free_symbol_set().iter().collect()
+    }
+
+    fn free_symbol_set(&self) -> ExprSet {
+        let set = ExprSet::new();
+        unsafe { basic_free_symbols(self.ptr, set.ptr) };
+        set
+    }
+
+    // =====================================================================
     // Solve polynomial
     // =====================================================================
 
     /// Solve a polynomial equation (self = 0) for the given symbol.
     /// Returns solutions as Vec<String>.
     pub fn solve_poly(&self, sym: &Expr) -> Vec<String> {
-        unsafe {
-            let set = setbasic_new();
-            basic_solve_poly(set, self.ptr, sym.ptr);
-            collect_set_strings(set)
-        }
+        self.solve_poly_set(sym).iter().map(|s| s.to_string()).collect()
+    }
+
+    fn solve_poly_set(&self, sym: &Expr) -> ExprSet {
+        let set = ExprSet::new();
+        unsafe { basic_solve_poly(set.ptr, self.ptr, sym.ptr) };
+        set
     }
 
     // =====================================================================
     // String representations
     // =====================================================================
-    str_fn!(to_string, basic_str);
     str_fn!(to_latex, basic_str_latex);
     str_fn!(to_mathml, basic_str_mathml);
     str_fn!(to_ccode, basic_str_ccode);
@@ -361,21 +1344,6 @@ impl Expr {
     }
 }
 
-/// Drain a CSetBasic into a Vec<String>, freeing the set.
-unsafe fn collect_set_strings(set: *mut CSetBasic) -> Vec<String> {
-    let n = setbasic_size(set);
-    let mut result = Vec::with_capacity(n);
-    let tmp = basic_new_heap();
-    for i in 0..n {
-        setbasic_get(set, i as c_int, tmp);
-        let s = basic_str(tmp);
-        result.push(CStr::from_ptr(s).to_string_lossy().into_owned());
-        basic_str_free(s);
-    }
-    basic_free_heap(tmp);
-    setbasic_free(set);
-    result
-}
 
 impl Drop for Expr {
     fn drop(&mut self) {
@@ -383,6 +1351,116 @@ impl Drop for Expr {
     }
 }
 
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = unsafe {
+            let s = basic_str(self.ptr);
+            let result = CStr::from_ptr(s).to_string_lossy().into_owned();
+            basic_str_free(s);
+            result
+        };
+        f.write_str(&text)
+    }
+}
+
+/// Shows the SymEngine type alongside the printed form, e.g.
+/// `Expr { type: "Add", value: "1 + x" }`.
+impl fmt::Debug for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Expr")
+            .field("type", &self.type_name())
+            .field("value", &self.to_string())
+            .finish()
+    }
+}
+
+/// Fallible parsing, so `let e: Expr = "x**2 + 1".parse()?` works.
+impl std::str::FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expr::try_parse(s)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Conversions from primitive types
+// ---------------------------------------------------------------------------
+
+impl From<i32> for Expr {
+    fn from(i: i32) -> Self {
+        Expr::integer(i)
+    }
+}
+
+/// Exact, via `Expr::integer_i64`.
+impl From<i64> for Expr {
+    fn from(i: i64) -> Self {
+        Expr::integer_i64(i)
+    }
+}
+
+/// Exact, via `Expr::integer_u64`.
+impl From<u64> for Expr {
+    fn from(u: u64) -> Self {
+        Expr::integer_u64(u)
+    }
+}
+
+/// A real double; NaN and the infinities map to SymEngine's `nan`, `oo`
+/// and `-oo` constants.
+impl From<f64> for Expr {
+    fn from(d: f64) -> Self {
+        if d.is_nan() {
+            Expr::nan()
+        } else if d == f64::INFINITY {
+            Expr::infinity()
+        } else if d == f64::NEG_INFINITY {
+            Expr::neg_infinity()
+        } else {
+            Expr::real_double(d)
+        }
+    }
+}
+
+/// The exact rational `p/q`.
+///
+/// Panics if `q` is zero.
+impl From<(i64, i64)> for Expr {
+    fn from((p, q): (i64, i64)) -> Self {
+        assert!(q != 0, "rational with zero denominator");
+        Expr::from(p).div(&Expr::from(q))
+    }
+}
+
+/// Parsing can fail, so strings convert with `TryFrom` (or `str::parse`)
+/// rather than `From`.
+impl TryFrom<&str> for Expr {
+    type Error = ParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Expr::try_parse(s)
+    }
+}
+
+/// Structural equality via `basic_eq`, so `x + 1` equals a separately parsed
+/// `1 + x` but not `(x**2 - 1)/(x - 1)`.
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        Expr::eq(self, other)
+    }
+}
+
+impl Eq for Expr {}
+
+/// Consistent with `PartialEq`: SymEngine's hash is structural, so equal
+/// expressions hash identically.
+impl Hash for Expr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash_value());
+    }
+}
+
 impl Clone for Expr {
     fn clone(&self) -> Self {
         unsafe {
@@ -393,6 +1471,311 @@ impl Clone for Expr {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Operator overloading: each operator delegates to the inherent method of the
+// same name, for every owned/borrowed operand combination.
+// ---------------------------------------------------------------------------
+
+macro_rules! impl_binary_operator {
+    ($trait:ident, $method:ident) => {
+        impl std::ops::$trait<&Expr> for &Expr {
+            type Output = Expr;
+            fn $method(self, rhs: &Expr) -> Expr {
+                Expr::$method(self, rhs)
+            }
+        }
+        impl std::ops::$trait<Expr> for &Expr {
+            type Output = Expr;
+            fn $method(self, rhs: Expr) -> Expr {
+                Expr::$method(self, &rhs)
+            }
+        }
+        impl std::ops::$trait<&Expr> for Expr {
+            type Output = Expr;
+            fn $method(self, rhs: &Expr) -> Expr {
+                Expr::$method(&self, rhs)
+            }
+        }
+        impl std::ops::$trait<Expr> for Expr {
+            type Output = Expr;
+            fn $method(self, rhs: Expr) -> Expr {
+                Expr::$method(&self, &rhs)
+            }
+        }
+    };
+}
+
+impl_binary_operator!(Add, add);
+impl_binary_operator!(Sub, sub);
+impl_binary_operator!(Mul, mul);
+impl_binary_operator!(Div, div);
+
+impl std::ops::Neg for &Expr {
+    type Output = Expr;
+    fn neg(self) -> Expr {
+        Expr::neg(self)
+    }
+}
+
+impl std::ops::Neg for Expr {
+    type Output = Expr;
+    fn neg(self) -> Expr {
+        Expr::neg(&self)
+    }
+}
+
+/// In-place assignment operators write the result into the left-hand
+/// operand's existing heap basic instead of allocating a new one.
+macro_rules! impl_assign_operator {
+    ($trait:ident, $method:ident, $ffi:ident) => {
+        impl std::ops::$trait<&Expr> for Expr {
+            fn $method(&mut self, rhs: &Expr) {
+                unsafe { $ffi(self.ptr, self.ptr, rhs.ptr) };
+            }
+        }
+        impl std::ops::$trait<Expr> for Expr {
+            fn $method(&mut self, rhs: Expr) {
+                unsafe { $ffi(self.ptr, self.ptr, rhs.ptr) };
+            }
+        }
+    };
+}
+
+impl_assign_operator!(AddAssign, add_assign, basic_add);
+impl_assign_operator!(MulAssign, mul_assign, basic_mul);
+
+// =========================================================================
+// Vector of expressions
+// =========================================================================
+
+/// An owned SymEngine `CVecBasic`. Every use of the `vecbasic_*` API goes
+/// through this type, so the vector is always freed exactly once.
+pub struct VecExpr {
+    ptr: *mut CVecBasic,
+}
+
+impl VecExpr {
+    pub fn new() -> Self {
+        Self { ptr: unsafe { vecbasic_new() } }
+    }
+
+    /// Append a copy of `e`.
+    pub fn push(&mut self, e: &Expr) {
+        unsafe { vecbasic_push_back(self.ptr, e.ptr) };
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { vecbasic_size(self.ptr) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy of the element at index `i`.
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Expr {
+        let len = self.len();
+        assert!(i < len, "index {} out of bounds for VecExpr of length {}", i, len);
+        let r = Expr { ptr: unsafe { basic_new_heap() } };
+        unsafe { vecbasic_get(self.ptr, i, r.ptr) };
+        r
+    }
+
+    /// Copies of the elements, in order.
+    pub fn iter(&self) -> impl Iterator<Item = Expr> + '_ {
+        (0..self.len()).map(move |i| self.get(i))
+    }
+}
+
+impl Default for VecExpr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&[Expr]> for VecExpr {
+    fn from(exprs: &[Expr]) -> Self {
+        let mut vec = Self::new();
+        for e in exprs {
+            vec.push(e);
+        }
+        vec
+    }
+}
+
+impl From<VecExpr> for Vec<Expr> {
+    fn from(vec: VecExpr) -> Self {
+        vec.iter().collect()
+    }
+}
+
+impl Drop for VecExpr {
+    fn drop(&mut self) {
+        unsafe { vecbasic_free(self.ptr) }
+    }
+}
+
+// =========================================================================
+// Set of expressions
+// =========================================================================
+
+/// An owned SymEngine `CSetBasic`: a set of expressions in SymEngine's
+/// canonical order.
+pub struct ExprSet {
+    ptr: *mut CSetBasic,
+}
+
+impl ExprSet {
+    pub fn new() -> Self {
+        Self { ptr: unsafe { setbasic_new() } }
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { setbasic_size(self.ptr) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert a copy of `e`. Returns whether it was not already present.
+    pub fn insert(&mut self, e: &Expr) -> bool {
+        unsafe { setbasic_insert(self.ptr, e.ptr) != 0 }
+    }
+
+    pub fn contains(&self, e: &Expr) -> bool {
+        unsafe { setbasic_find(self.ptr, e.ptr) != 0 }
+    }
+
+    /// Copies of the elements, in set order.
+    pub fn iter(&self) -> impl Iterator<Item = Expr> + '_ {
+        (0..self.len()).map(move |i| {
+            let r = Expr { ptr: unsafe { basic_new_heap() } };
+            unsafe { setbasic_get(self.ptr, i as c_int, r.ptr) };
+            r
+        })
+    }
+}
+
+impl Default for ExprSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ExprSet {
+    fn drop(&mut self) {
+        unsafe { setbasic_free(self.ptr) }
+    }
+}
+
+// =========================================================================
+// Substitution table
+// =========================================================================
+
+/// An owned SymEngine `CMapBasicBasic` of replacements for `Expr::subs_with`.
+/// Keys and values are copied in, so the map does not borrow from the
+/// expressions it was built from.
+pub struct SubsMap {
+    ptr: *mut CMapBasicBasic,
+}
+
+impl SubsMap {
+    pub fn new() -> Self {
+        Self { ptr: unsafe { mapbasicbasic_new() } }
+    }
+
+    /// Replace `from` with `to`, overwriting any earlier entry for `from`.
+    pub fn insert(&mut self, from: &Expr, to: &Expr) {
+        unsafe { mapbasicbasic_insert(self.ptr, from.ptr, to.ptr) };
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { mapbasicbasic_size(self.ptr) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for SubsMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SubsMap {
+    fn drop(&mut self) {
+        unsafe { mapbasicbasic_free(self.ptr) }
+    }
+}
+
+// =========================================================================
+// Shared expressions without thread-safe RCP
+// =========================================================================
+
+/// Serialises every `SyncExpr` access, including drops. Without
+/// `WITH_SYMENGINE_THREAD_SAFE` even unrelated expressions share nodes such
+/// as the cached integers, so a per-value lock would not be enough.
+static SYMENGINE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// An `Expr` behind a process-wide lock, for sharing between threads when
+/// SymEngine's reference counts are not atomic. The lock only covers
+/// `SyncExpr` itself, so construction is `unsafe`; see [`SyncExpr::new`].
+/// With the `thread-safe` feature, share `Expr` directly instead.
+pub struct SyncExpr {
+    inner: std::mem::ManuallyDrop<Expr>,
+}
+
+// Every touch of `inner`, including the final drop, happens under the lock.
+unsafe impl Sync for SyncExpr {}
+
+impl SyncExpr {
+    /// Wrap `expr` for sharing.
+    ///
+    /// # Safety
+    ///
+    /// While a `SyncExpr` is reachable from more than one thread, SymEngine
+    /// may only be touched inside [`SyncExpr::with`]. That includes building,
+    /// cloning or dropping an unrelated `Expr`, since even those share cached
+    /// nodes, and any `Expr` cloned inside `with` must not leave the closure.
+    pub unsafe fn new(expr: Expr) -> Self {
+        Self { inner: std::mem::ManuallyDrop::new(expr) }
+    }
+
+    /// Run `f` on the expression while holding the lock. Return plain data
+    /// (strings, numbers, bools); returning an `Expr` breaks the contract of
+    /// [`SyncExpr::new`].
+    pub fn with<R>(&self, f: impl FnOnce(&Expr) -> R) -> R {
+        let _guard = SYMENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        f(&self.inner)
+    }
+
+    /// Take the expression back out, for use on the current thread.
+    pub fn into_inner(self) -> Expr {
+        let _guard = SYMENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut this = std::mem::ManuallyDrop::new(self);
+        unsafe { std::mem::ManuallyDrop::take(&mut this.inner) }
+    }
+}
+
+impl fmt::Display for SyncExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = self.with(|e| e.to_string());
+        f.write_str(&s)
+    }
+}
+
+impl Drop for SyncExpr {
+    fn drop(&mut self) {
+        let _guard = SYMENGINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        unsafe { std::mem::ManuallyDrop::drop(&mut self.inner) }
+    }
+}
+
 // =========================================================================
 // Number theory (free functions)
 // =========================================================================
@@ -453,82 +1836,484 @@ pub fn binomial(n: &Expr, k: u32) -> Expr {
     }
 }
 
-// =========================================================================
-// Dense matrix wrapper
-// =========================================================================
+/// Integer remainder of `n / d`. Both arguments must be integers and `d` non-zero.
+pub fn ntheory_mod(n: &Expr, d: &Expr) -> Expr {
+    unsafe {
+        let r = basic_new_heap();
+        crate::symengine_ffi::ntheory_mod(r, n.as_ptr(), d.as_ptr());
+        Expr { ptr: r }
+    }
+}
 
-pub struct Matrix {
-    ptr: *mut CDenseMatrix,
+/// Integer quotient of `n / d`. Both arguments must be integers and `d` non-zero.
+pub fn ntheory_quotient(n: &Expr, d: &Expr) -> Expr {
+    unsafe {
+        let r = basic_new_heap();
+        crate::symengine_ffi::ntheory_quotient(r, n.as_ptr(), d.as_ptr());
+        Expr { ptr: r }
+    }
 }
 
-impl Matrix {
-    /// Create a matrix from a flat vector of expressions, given rows × cols.
-    pub fn from_vec(rows: u32, cols: u32, elements: &[Expr]) -> Self {
-        unsafe {
-            let mat = dense_matrix_new_rows_cols(rows as _, cols as _);
-            for (i, e) in elements.iter().enumerate() {
-                let r = (i as u32) / cols;
-                let c = (i as u32) % cols;
-                dense_matrix_set_basic(mat, r as _, c as _, e.as_ptr());
-            }
-            Self { ptr: mat }
+/// Modular inverse of `a` modulo `m`, or `None` when `gcd(a, m) != 1`.
+/// Both arguments must be integers.
+pub fn mod_inverse(a: &Expr, m: &Expr) -> Option<Expr> {
+    unsafe {
+        let r = basic_new_heap();
+        let found = ntheory_mod_inverse(r, a.as_ptr(), m.as_ptr());
+        let r = Expr { ptr: r };
+        if found != 0 {
+            Some(r)
+        } else {
+            None
         }
     }
+}
 
-    pub fn rows(&self) -> u32 {
-        unsafe { dense_matrix_rows(self.ptr) as u32 }
-    }
+// =========================================================================
+// Solvers (free functions)
+// =========================================================================
 
-    pub fn cols(&self) -> u32 {
-        unsafe { dense_matrix_cols(self.ptr) as u32 }
-    }
+/// Solve the linear system `equations = 0` for `symbols`. Solutions come
+/// back in the same order as `symbols`.
+pub fn linsolve(equations: &[Expr], symbols: &[Expr]) -> Vec<Expr> {
+    let sys = VecExpr::from(equations);
+    let syms = VecExpr::from(symbols);
+    let sol = VecExpr::new();
+    unsafe { vecbasic_linsolve(sol.ptr, sys.ptr, syms.ptr) };
+    sol.into()
+}
 
-    pub fn get(&self, r: u32, c: u32) -> Expr {
-        unsafe {
-            let e = basic_new_heap();
-            dense_matrix_get_basic(e, self.ptr, r as _, c as _);
-            Expr { ptr: e }
-        }
+/// Find a root of `expr = 0` near `x0` by Newton's method. The derivative is
+/// taken symbolically once; each step substitutes a `RealDouble` for `var`
+/// and extracts the value with `to_f64`. Stops when a step is smaller than
+/// `tol` in absolute value.
+pub fn newton_solve(
+    expr: &Expr,
+    var: &Expr,
+    x0: f64,
+    tol: f64,
+    max_iter: u32,
+) -> Result<f64, SolveError> {
+    if !var.is_symbol() {
+        return Err(SolveError::NotSymbol);
     }
-
-    pub fn det(&self) -> Expr {
-        unsafe {
-            let r = basic_new_heap();
-            dense_matrix_det(r, self.ptr);
-            Expr { ptr: r }
-        }
+    let name = var.to_string();
+    let extra: Vec<String> = expr.free_symbols().into_iter().filter(|s| *s != name).collect();
+    if !extra.is_empty() {
+        return Err(SolveError::FreeSymbols(extra));
     }
-
-    pub fn inv(&self) -> Self {
-        unsafe {
-            let r = dense_matrix_new();
-            dense_matrix_inv(r, self.ptr);
-            Self { ptr: r }
+    let d = expr.diff(var);
+    let at = |e: &Expr, x: f64| e.subs(var, &Expr::real_double(x)).to_f64();
+    let mut x = x0;
+    for iterations in 0..max_iter {
+        let fx = at(expr, x).ok_or(SolveError::NotReal { iterations })?;
+        if fx == 0.0 {
+            return Ok(x);
+        }
+        let dx = at(&d, x).ok_or(SolveError::NotReal { iterations })?;
+        if dx == 0.0 {
+            return Err(SolveError::ZeroDerivative { iterations });
+        }
+        let step = fx / dx;
+        x -= step;
+        if !x.is_finite() {
+            return Err(SolveError::NoConvergence { iterations: iterations + 1 });
+        }
+        if step.abs() < tol {
+            return Ok(x);
         }
     }
+    Err(SolveError::NoConvergence { iterations: max_iter })
+}
 
-    pub fn transpose(&self) -> Self {
+// =========================================================================
+// Common subexpression elimination (free functions)
+// =========================================================================
+
+/// Common subexpression elimination over all of `exprs` at once. Returns
+/// the `(symbol, subexpression)` replacements, in the order they must be
+/// computed, and the reduced expressions written in terms of those symbols.
+pub fn cse(exprs: &[Expr]) -> (Vec<(Expr, Expr)>, Vec<Expr>) {
+    let input = VecExpr::from(exprs);
+    let syms = VecExpr::new();
+    let subexprs = VecExpr::new();
+    let reduced = VecExpr::new();
+    unsafe { basic_cse(syms.ptr, subexprs.ptr, reduced.ptr, input.ptr) };
+    let replacements = syms.iter().zip(subexprs.iter()).collect();
+    (replacements, reduced.into())
+}
+
+// =========================================================================
+// Sums and products (free functions)
+// =========================================================================
+
+/// Sum of all expressions in one `basic_add_vec` call; 0 for an empty slice.
+pub fn add_all(exprs: &[Expr]) -> Expr {
+    if exprs.is_empty() {
+        return Expr::zero();
+    }
+    unsafe { reduce_vec(exprs, basic_add_vec) }
+}
+
+/// Product of all expressions in one `basic_mul_vec` call; 1 for an empty
+/// slice.
+pub fn mul_all(exprs: &[Expr]) -> Expr {
+    if exprs.is_empty() {
+        return Expr::one();
+    }
+    unsafe { reduce_vec(exprs, basic_mul_vec) }
+}
+
+impl std::iter::Sum for Expr {
+    fn sum<I: Iterator<Item = Expr>>(iter: I) -> Self {
+        add_all(&iter.collect::<Vec<_>>())
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Expr> for Expr {
+    fn sum<I: Iterator<Item = &'a Expr>>(iter: I) -> Self {
+        add_all(&iter.cloned().collect::<Vec<_>>())
+    }
+}
+
+impl std::iter::Product for Expr {
+    fn product<I: Iterator<Item = Expr>>(iter: I) -> Self {
+        mul_all(&iter.collect::<Vec<_>>())
+    }
+}
+
+impl<'a> std::iter::Product<&'a Expr> for Expr {
+    fn product<I: Iterator<Item = &'a Expr>>(iter: I) -> Self {
+        mul_all(&iter.cloned().collect::<Vec<_>>())
+    }
+}
+
+// =========================================================================
+// Max / min (free functions)
+// =========================================================================
+
+/// Symbolic maximum of a non-empty list of expressions.
+pub fn max(exprs: &[Expr]) -> Expr {
+    assert!(!exprs.is_empty(), "max() of an empty list");
+    unsafe { reduce_vec(exprs, basic_max) }
+}
+
+/// Symbolic minimum of a non-empty list of expressions.
+pub fn min(exprs: &[Expr]) -> Expr {
+    assert!(!exprs.is_empty(), "min() of an empty list");
+    unsafe { reduce_vec(exprs, basic_min) }
+}
+
+/// Collect `exprs` into a temporary VecExpr and reduce it with `f`.
+unsafe fn reduce_vec(
+    exprs: &[Expr],
+    f: unsafe extern "C" fn(*mut BasicStruct, *const CVecBasic) -> c_int,
+) -> Expr {
+    let vec = VecExpr::from(exprs);
+    let r = Expr { ptr: basic_new_heap() };
+    f(r.ptr, vec.ptr);
+    r
+}
+
+// =========================================================================
+// Vector products (free functions)
+// =========================================================================
+
+/// Dot product `Σ a[i] * b[i]` of two equal-length vectors.
+pub fn dot(a: &[Expr], b: &[Expr]) -> Result<Expr, MatrixError> {
+    if a.len() != b.len() {
+        return Err(MatrixError::LengthMismatch { left: a.len(), right: b.len() });
+    }
+    Ok(a.iter().zip(b).map(|(x, y)| x.mul(y)).sum())
+}
+
+/// Cross product of two 3-vectors.
+pub fn cross(a: &[Expr; 3], b: &[Expr; 3]) -> [Expr; 3] {
+    let component = |i: usize, j: usize| a[i].mul(&b[j]).sub(&a[j].mul(&b[i]));
+    [component(1, 2), component(2, 0), component(0, 1)]
+}
+
+// =========================================================================
+// Dense matrix wrapper
+// =========================================================================
+
+pub struct Matrix {
+    ptr: *mut CDenseMatrix,
+}
+
+impl Matrix {
+    /// Create a matrix from a row-major list of exactly rows × cols
+    /// expressions.
+    pub fn from_vec(rows: u32, cols: u32, elements: &[Expr]) -> Result<Self, MatrixError> {
+        if elements.len() as u64 != u64::from(rows) * u64::from(cols) {
+            return Err(MatrixError::ElementCount { rows, cols, len: elements.len() });
+        }
+        Ok(Self::from_entries(rows, cols, elements))
+    }
+
+    /// Create a matrix from a list of rows, taking the shape from the input.
+    /// An empty list gives a 0×0 matrix.
+    pub fn from_nested(rows: &[Vec<Expr>]) -> Result<Self, MatrixError> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if let Some((row, r)) = rows.iter().enumerate().find(|(_, r)| r.len() != cols) {
+            return Err(MatrixError::Ragged { row, len: r.len(), expected: cols });
+        }
+        let elements: Vec<Expr> = rows.iter().flatten().cloned().collect();
+        Ok(Self::from_entries(rows.len() as u32, cols as u32, &elements))
+    }
+
+    /// `from_vec` for callers that already have rows × cols elements.
+    ///
+    /// Panics if the element count is wrong, rather than leaving entries
+    /// unset.
+    fn from_entries(rows: u32, cols: u32, elements: &[Expr]) -> Self {
+        assert_eq!(
+            elements.len(),
+            rows as usize * cols as usize,
+            "element count for a {}x{} matrix",
+            rows,
+            cols
+        );
         unsafe {
-            let r = dense_matrix_new();
-            dense_matrix_transpose(r, self.ptr);
-            Self { ptr: r }
+            let mat = dense_matrix_new_rows_cols(rows as _, cols as _);
+            for (i, e) in elements.iter().enumerate() {
+                let r = (i as u32) / cols;
+                let c = (i as u32) % cols;
+                dense_matrix_set_basic(mat, r as _, c as _, e.as_ptr());
+            }
+            Self { ptr: mat }
         }
     }
 
-    pub fn add(&self, other: &Matrix) -> Self {
+    /// rows × cols matrix whose entry at (r, c) is `f(r, c)`. `f` is called
+    /// in row-major order. The n×n Hilbert matrix, for example, is
+    /// `from_fn(n, n, |r, c| Expr::rational(1, (r + c + 1) as i32))`.
+    pub fn from_fn(rows: u32, cols: u32, mut f: impl FnMut(u32, u32) -> Expr) -> Self {
+        let elements: Vec<Expr> = (0..rows)
+            .flat_map(|r| (0..cols).map(move |c| (r, c)))
+            .map(|(r, c)| f(r, c))
+            .collect();
+        Self::from_entries(rows, cols, &elements)
+    }
+
+    /// n×n identity matrix.
+    pub fn identity(n: u32) -> Self {
+        Self::diag(&vec![Expr::one(); n as usize])
+    }
+
+    /// rows × cols matrix of zeros.
+    pub fn zeros(rows: u32, cols: u32) -> Self {
+        Self::filled(rows, cols, &Expr::zero())
+    }
+
+    /// rows × cols matrix of ones.
+    pub fn ones(rows: u32, cols: u32) -> Self {
+        Self::filled(rows, cols, &Expr::one())
+    }
+
+    /// Square matrix with `entries` on the diagonal and zeros elsewhere.
+    pub fn diag(entries: &[Expr]) -> Self {
+        let n = entries.len() as u32;
+        let mut m = Self::zeros(n, n);
+        for (i, e) in (0..n).zip(entries) {
+            m.set(i, i, e);
+        }
+        m
+    }
+
+    fn filled(rows: u32, cols: u32, value: &Expr) -> Self {
+        Self::from_entries(rows, cols, &vec![value.clone(); rows as usize * cols as usize])
+    }
+
+    pub fn rows(&self) -> u32 {
+        unsafe { dense_matrix_rows(self.ptr) as u32 }
+    }
+
+    pub fn cols(&self) -> u32 {
+        unsafe { dense_matrix_cols(self.ptr) as u32 }
+    }
+
+    /// (rows, cols).
+    pub fn shape(&self) -> (u32, u32) {
+        (self.rows(), self.cols())
+    }
+
+    /// The entry at row r, column c.
+    ///
+    /// Panics if the position is outside the matrix.
+    pub fn get(&self, r: u32, c: u32) -> Expr {
+        match self.try_get(r, c) {
+            Ok(e) => e,
+            Err(_) => panic!(
+                "position ({}, {}) outside {}x{} matrix",
+                r,
+                c,
+                self.rows(),
+                self.cols()
+            ),
+        }
+    }
+
+    /// Like `get`, but reports an out-of-range row (checked first) or
+    /// column as `OutOfRange`.
+    pub fn try_get(&self, r: u32, c: u32) -> Result<Expr, MatrixError> {
+        check_index(r, self.rows())?;
+        check_index(c, self.cols())?;
         unsafe {
-            let r = dense_matrix_new();
-            dense_matrix_add_matrix(r, self.ptr, other.ptr);
-            Self { ptr: r }
+            let e = basic_new_heap();
+            dense_matrix_get_basic(e, self.ptr, r as _, c as _);
+            Ok(Expr { ptr: e })
         }
     }
 
-    pub fn mul(&self, other: &Matrix) -> Self {
+    /// Overwrite the entry at row r, column c.
+    ///
+    /// Panics if the position is outside the matrix.
+    pub fn set(&mut self, r: u32, c: u32, value: &Expr) {
+        assert!(
+            r < self.rows() && c < self.cols(),
+            "position ({}, {}) outside {}x{} matrix",
+            r,
+            c,
+            self.rows(),
+            self.cols()
+        );
+        unsafe { dense_matrix_set_basic(self.ptr, r as _, c as _, value.as_ptr()) };
+    }
+
+    /// Determinant.
+    ///
+    /// Panics if the matrix is not square; see `try_det`.
+    pub fn det(&self) -> Expr {
+        self.try_det().unwrap_or_else(|e| panic!("cannot take determinant: {}", e))
+    }
+
+    /// Determinant, rejecting non-square input.
+    pub fn try_det(&self) -> Result<Expr, MatrixError> {
+        self.check_square()?;
         unsafe {
-            let r = dense_matrix_new();
-            dense_matrix_mul_matrix(r, self.ptr, other.ptr);
-            Self { ptr: r }
+            let r = Expr { ptr: basic_new_heap() };
+            check(dense_matrix_det(r.ptr, self.ptr))?;
+            Ok(r)
+        }
+    }
+
+    /// Inverse, rejecting non-square input and singular matrices.
+    /// Singularity is decided as in `rank`.
+    pub fn try_inv(&self) -> Result<Self, MatrixError> {
+        self.check_square()?;
+        if self.rank() < self.rows() {
+            return Err(MatrixError::Singular);
+        }
+        unsafe {
+            let r = Self { ptr: dense_matrix_new() };
+            check(dense_matrix_inv(r.ptr, self.ptr))?;
+            Ok(r)
+        }
+    }
+
+    /// Product, rejecting shapes where `self.cols() != other.rows()`.
+    pub fn try_mul(&self, other: &Matrix) -> Result<Self, MatrixError> {
+        if self.cols() != other.rows() {
+            return Err(self.shape_mismatch(other));
+        }
+        unsafe {
+            let r = Self { ptr: dense_matrix_new() };
+            check(dense_matrix_mul_matrix(r.ptr, self.ptr, other.ptr))?;
+            Ok(r)
+        }
+    }
+
+    /// Element-wise sum, rejecting matrices of different shapes.
+    pub fn try_add(&self, other: &Matrix) -> Result<Self, MatrixError> {
+        if self.shape() != other.shape() {
+            return Err(self.shape_mismatch(other));
+        }
+        unsafe {
+            let r = Self { ptr: dense_matrix_new() };
+            check(dense_matrix_add_matrix(r.ptr, self.ptr, other.ptr))?;
+            Ok(r)
+        }
+    }
+
+    /// Element-wise difference, rejecting matrices of different shapes.
+    pub fn try_sub(&self, other: &Matrix) -> Result<Self, MatrixError> {
+        self.try_add(&other.mul_scalar(&Expr::minus_one()))
+    }
+
+    /// Transpose. Every shape is valid, so this only fails if SymEngine
+    /// reports an error.
+    pub fn try_transpose(&self) -> Result<Self, MatrixError> {
+        unsafe {
+            let r = Self { ptr: dense_matrix_new() };
+            check(dense_matrix_transpose(r.ptr, self.ptr))?;
+            Ok(r)
+        }
+    }
+
+    /// Inverse.
+    ///
+    /// Panics if the matrix is not square or is singular; see `try_inv`.
+    pub fn inv(&self) -> Self {
+        self.try_inv().unwrap_or_else(|e| panic!("cannot invert matrix: {}", e))
+    }
+
+    pub fn transpose(&self) -> Self {
+        self.try_transpose().unwrap_or_else(|e| panic!("cannot transpose matrix: {}", e))
+    }
+
+    /// Element-wise sum.
+    ///
+    /// Panics if the shapes differ; see `try_add`.
+    pub fn add(&self, other: &Matrix) -> Self {
+        self.try_add(other).unwrap_or_else(|e| panic!("cannot add matrices: {}", e))
+    }
+
+    /// Element-wise difference. The cwrapper has no subtraction, so this adds
+    /// `-1 * other`; integer and rational entries stay exact.
+    ///
+    /// Panics if the shapes differ; see `try_sub`.
+    pub fn sub(&self, other: &Matrix) -> Self {
+        self.try_sub(other).unwrap_or_else(|e| panic!("cannot subtract matrices: {}", e))
+    }
+
+    /// Matrix product.
+    ///
+    /// Panics if `self.cols() != other.rows()`; see `try_mul`.
+    pub fn mul(&self, other: &Matrix) -> Self {
+        self.try_mul(other).unwrap_or_else(|e| panic!("cannot multiply matrices: {}", e))
+    }
+
+    fn check_square(&self) -> Result<(), MatrixError> {
+        if self.rows() != self.cols() {
+            return Err(MatrixError::NotSquare { rows: self.rows(), cols: self.cols() });
+        }
+        Ok(())
+    }
+
+    /// `self` raised to the n-th power by repeated squaring; `n = 0` gives
+    /// the identity.
+    pub fn pow(&self, n: u32) -> Result<Matrix, MatrixError> {
+        let size = self.rows();
+        if size != self.cols() {
+            return Err(MatrixError::NotSquare { rows: size, cols: self.cols() });
+        }
+        let mut result: Option<Matrix> = None;
+        let mut base = self.clone();
+        let mut n = n;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = Some(match result {
+                    Some(r) => r.mul(&base),
+                    None => base.clone(),
+                });
+            }
+            n >>= 1;
+            if n > 0 {
+                base = base.mul(&base);
+            }
         }
+        Ok(result.unwrap_or_else(|| Matrix::identity(size)))
     }
 
     pub fn mul_scalar(&self, s: &Expr) -> Self {
@@ -539,7 +2324,313 @@ impl Matrix {
         }
     }
 
-    pub fn to_string(&self) -> String {
+    /// Rank over the exact (symbolic) entries, by fraction-free Gaussian
+    /// elimination. Entries are expanded before each zero test, so an entry
+    /// counts as zero only if it expands to literal 0; expressions that vanish
+    /// only through other identities (e.g. `sin(x)**2 + cos(x)**2 - 1`) are
+    /// treated as nonzero, which can overstate the rank.
+    pub fn rank(&self) -> u32 {
+        self.eliminate().0
+    }
+
+    /// Fraction-free (Bareiss) elimination with row swaps. Returns the rank
+    /// and the order in which the original rows ended up.
+    fn eliminate(&self) -> (u32, Vec<usize>) {
+        let (rows, cols) = (self.rows() as usize, self.cols() as usize);
+        let mut a = self.expanded_rows();
+        let mut order: Vec<usize> = (0..rows).collect();
+        let mut rank = 0;
+        let mut prev = Expr::one();
+        for c in 0..cols {
+            if rank == rows {
+                break;
+            }
+            let Some(p) = (rank..rows).find(|&r| !a[r][c].is_zero()) else {
+                continue;
+            };
+            a.swap(rank, p);
+            order.swap(rank, p);
+            bareiss_step(&mut a, rank, c, &prev);
+            prev = a[rank][c].clone();
+            rank += 1;
+        }
+        (rank as u32, order)
+    }
+
+    /// Structural equality: same shape and equal entries. Matrices of
+    /// different shapes are simply unequal.
+    pub fn eq(&self, other: &Matrix) -> bool {
+        (self.rows(), self.cols()) == (other.rows(), other.cols())
+            && unsafe { dense_matrix_eq(self.ptr, other.ptr) != 0 }
+    }
+
+    /// Equality after expanding every entry, so `[[(x+1)**2]]` equals
+    /// `[[x**2 + 2*x + 1]]`.
+    pub fn eq_expanded(&self, other: &Matrix) -> bool {
+        self.expand().eq(&other.expand())
+    }
+
+    /// Substitute `to` for `from` in every entry.
+    pub fn subs(&self, from: &Expr, to: &Expr) -> Self {
+        self.map(|e| e.subs(from, to))
+    }
+
+    /// Simultaneous substitution in every entry; see `Expr::subs_map`.
+    pub fn subs_map(&self, pairs: &[(&Expr, &Expr)]) -> Self {
+        let mut map = SubsMap::new();
+        for (k, v) in pairs {
+            map.insert(k, v);
+        }
+        self.map(|e| e.subs_with(&map))
+    }
+
+    /// Apply `f` to every entry, keeping the shape.
+    pub fn map(&self, f: impl Fn(&Expr) -> Expr) -> Self {
+        self.map_indexed(|_, _, e| f(e))
+    }
+
+    /// Like `map`, but `f` also gets the row and column, e.g.
+    /// `m.map_indexed(|r, c, e| if r > c { Expr::zero() } else { e.clone() })`
+    /// keeps the upper triangle. Entries are visited in row-major order and
+    /// written straight into the result.
+    pub fn map_indexed(&self, f: impl Fn(u32, u32, &Expr) -> Expr) -> Self {
+        let (rows, cols) = self.shape();
+        let mut out = unsafe { Self { ptr: dense_matrix_new_rows_cols(rows as _, cols as _) } };
+        for r in 0..rows {
+            for c in 0..cols {
+                out.set(r, c, &f(r, c, &self.get(r, c)));
+            }
+        }
+        out
+    }
+
+    /// Squared Frobenius norm `Σ |a_ij|²`. Uses `abs`, so complex entries
+    /// contribute `|z|²` rather than `z²`; rational entries stay rational.
+    pub fn frobenius_norm_squared(&self) -> Expr {
+        let two = Expr::integer(2);
+        self.to_vec().iter().map(|e| e.abs().pow(&two)).sum()
+    }
+
+    /// Frobenius norm, the square root of `frobenius_norm_squared`.
+    pub fn frobenius_norm(&self) -> Expr {
+        self.frobenius_norm_squared().sqrt()
+    }
+
+    /// Symbolic maximum of `|a_ij|` over all entries; zero for an empty matrix.
+    pub fn max_abs_entry(&self) -> Expr {
+        let abs: Vec<Expr> = self.to_vec().iter().map(Expr::abs).collect();
+        if abs.is_empty() {
+            return Expr::zero();
+        }
+        max(&abs)
+    }
+
+    /// Entries in row-major order.
+    pub fn to_vec(&self) -> Vec<Expr> {
+        self.iter_elements().map(|(_, e)| e).collect()
+    }
+
+    /// `((row, col), entry)` for every entry, in row-major order.
+    pub fn iter_elements(&self) -> impl Iterator<Item = ((u32, u32), Expr)> + '_ {
+        let (rows, cols) = self.shape();
+        (0..rows)
+            .flat_map(move |r| (0..cols).map(move |c| (r, c)))
+            .map(|(r, c)| ((r, c), self.get(r, c)))
+    }
+
+    /// Each row as a `Vec`, top to bottom.
+    pub fn iter_rows(&self) -> impl Iterator<Item = Vec<Expr>> + '_ {
+        let cols = self.cols();
+        (0..self.rows()).map(move |r| (0..cols).map(|c| self.get(r, c)).collect())
+    }
+
+    /// Free symbols of all entries together, sorted and without duplicates.
+    pub fn free_symbols(&self) -> Vec<String> {
+        let names: std::collections::BTreeSet<String> = self
+            .iter_elements()
+            .flat_map(|(_, e)| e.free_symbols())
+            .collect();
+        names.into_iter().collect()
+    }
+
+    /// Copy of `self` with every entry expanded.
+    pub fn expand(&self) -> Self {
+        let elements: Vec<Expr> = self.expanded_rows().into_iter().flatten().collect();
+        Self::from_entries(self.rows(), self.cols(), &elements)
+    }
+
+    /// Entries as a vector of rows, each entry expanded.
+    fn expanded_rows(&self) -> Vec<Vec<Expr>> {
+        (0..self.rows())
+            .map(|r| (0..self.cols()).map(|c| self.get(r, c).expand()).collect())
+            .collect()
+    }
+
+    /// Reduced row echelon form, by Gauss–Jordan elimination over exact
+    /// entries. A pivot is the first entry in its column that does not expand
+    /// to 0; a symbolic pivot is assumed nonzero, so the result holds only
+    /// where it is (e.g. `[["a","1"]]` reduces to `[["1","1/a"]]`).
+    pub fn rref(&self) -> Self {
+        let (rows, cols) = (self.rows() as usize, self.cols() as usize);
+        let mut a = self.expanded_rows();
+        let mut r = 0;
+        for c in 0..cols {
+            if r == rows {
+                break;
+            }
+            let Some(p) = (r..rows).find(|&i| !a[i][c].is_zero()) else {
+                continue;
+            };
+            a.swap(r, p);
+            let pivot = a[r][c].clone();
+            for x in &mut a[r][c..] {
+                *x = x.div(&pivot).expand();
+            }
+            let pivot_row = a[r].clone();
+            for (i, row) in a.iter_mut().enumerate() {
+                if i == r || row[c].is_zero() {
+                    continue;
+                }
+                let factor = row[c].clone();
+                for (x, p) in row[c..].iter_mut().zip(&pivot_row[c..]) {
+                    *x = x.sub(&factor.mul(p)).expand();
+                }
+            }
+            r += 1;
+        }
+        let elements: Vec<Expr> = a.into_iter().flatten().collect();
+        Self::from_entries(rows as u32, cols as u32, &elements)
+    }
+
+    /// Whether elimination without row swaps meets a nonzero pivot at every
+    /// step but the last, i.e. whether the leading principal minors are
+    /// nonzero. Zero tests follow `rank`.
+    fn pivots_without_swaps(&self) -> bool {
+        let n = self.rows() as usize;
+        let mut a = self.expanded_rows();
+        let mut prev = Expr::one();
+        for c in 0..n.saturating_sub(1) {
+            if a[c][c].is_zero() {
+                return false;
+            }
+            bareiss_step(&mut a, c, c, &prev);
+            prev = a[c][c].clone();
+        }
+        true
+    }
+
+    /// Copy of `self` with its rows taken in the given order.
+    fn permute_rows(&self, order: &[usize]) -> Self {
+        let cols = self.cols();
+        let elements: Vec<Expr> = order
+            .iter()
+            .flat_map(|&r| (0..cols).map(move |c| self.get(r as u32, c)))
+            .collect();
+        Self::from_entries(order.len() as u32, cols, &elements)
+    }
+
+    /// LU factorization `self = L * U` with L unit lower triangular and U
+    /// upper triangular. SymEngine does not pivot, so matrices whose leading
+    /// principal minors vanish are rejected with `ZeroPivot`.
+    pub fn lu(&self) -> Result<(Matrix, Matrix), MatrixError> {
+        let n = self.check_factorable()?;
+        unsafe {
+            let l = Self { ptr: dense_matrix_new_rows_cols(n as _, n as _) };
+            let u = Self { ptr: dense_matrix_new_rows_cols(n as _, n as _) };
+            dense_matrix_LU(l.ptr, u.ptr, self.ptr);
+            Ok((l, u))
+        }
+    }
+
+    /// Fraction-free LU factorization, with L and U packed into one matrix
+    /// (the strictly lower part is L, the rest U). Integer input gives
+    /// integer output, avoiding the rational growth of plain LU.
+    pub fn fflu(&self) -> Result<Matrix, MatrixError> {
+        let n = self.check_factorable()?;
+        unsafe {
+            let lu = Self { ptr: dense_matrix_new_rows_cols(n as _, n as _) };
+            dense_matrix_FFLU(lu.ptr, self.ptr);
+            Ok(lu)
+        }
+    }
+
+    /// Fraction-free LDU factorization, returning `(L, D, U)`.
+    pub fn ffldu(&self) -> Result<(Matrix, Matrix, Matrix), MatrixError> {
+        let n = self.check_factorable()?;
+        unsafe {
+            let l = Self { ptr: dense_matrix_new_rows_cols(n as _, n as _) };
+            let d = Self { ptr: dense_matrix_new_rows_cols(n as _, n as _) };
+            let u = Self { ptr: dense_matrix_new_rows_cols(n as _, n as _) };
+            dense_matrix_FFLDU(l.ptr, d.ptr, u.ptr, self.ptr);
+            Ok((l, d, u))
+        }
+    }
+
+    /// Shared checks for the unpivoted factorizations: square, with no zero
+    /// pivot along the way. Returns the dimension.
+    fn check_factorable(&self) -> Result<u32, MatrixError> {
+        let n = self.rows();
+        if n != self.cols() {
+            return Err(MatrixError::NotSquare { rows: n, cols: self.cols() });
+        }
+        if !self.pivots_without_swaps() {
+            return Err(MatrixError::ZeroPivot);
+        }
+        Ok(n)
+    }
+
+    /// LDL factorization `self = L * D * Lᵀ` of a symmetric matrix, with L
+    /// unit lower triangular and D diagonal. Entries stay rational where a
+    /// Cholesky factorization would need square roots. Symmetry is checked
+    /// entry by entry after expansion; zero pivots are rejected as in `lu`.
+    pub fn ldl(&self) -> Result<(Matrix, Matrix), MatrixError> {
+        let n = self.rows();
+        if n != self.cols() {
+            return Err(MatrixError::NotSquare { rows: n, cols: self.cols() });
+        }
+        let symmetric = (0..n).all(|r| {
+            (0..r).all(|c| self.get(r, c).sub(&self.get(c, r)).expand().is_zero())
+        });
+        if !symmetric {
+            return Err(MatrixError::NotSymmetric);
+        }
+        self.check_factorable()?;
+        unsafe {
+            let l = Self { ptr: dense_matrix_new_rows_cols(n as _, n as _) };
+            let d = Self { ptr: dense_matrix_new_rows_cols(n as _, n as _) };
+            dense_matrix_LDL(l.ptr, d.ptr, self.ptr);
+            Ok((l, d))
+        }
+    }
+
+    /// Solve `self * x = b` for x, where `b` is n×k (one column per
+    /// right-hand side). Rows are reordered first so that SymEngine's
+    /// unpivoted LU never meets a zero pivot; singularity is detected the
+    /// same way as in `rank`, so it is subject to the same caveat.
+    pub fn solve(&self, b: &Matrix) -> Result<Matrix, MatrixError> {
+        let n = self.rows();
+        if n != self.cols() {
+            return Err(MatrixError::NotSquare { rows: n, cols: self.cols() });
+        }
+        if b.rows() != n {
+            return Err(self.shape_mismatch(b));
+        }
+        let (rank, order) = self.eliminate();
+        if rank < n {
+            return Err(MatrixError::Singular);
+        }
+        let pa = self.permute_rows(&order);
+        let pb = b.permute_rows(&order);
+        unsafe {
+            let x = dense_matrix_new_rows_cols(n as _, b.cols() as _);
+            dense_matrix_LU_solve(x, pa.ptr, pb.ptr);
+            Ok(Self { ptr: x })
+        }
+    }
+
+    /// SymEngine's own `dense_matrix_str` form, one bracketed row per line
+    /// with entries separated by `, ` and no column alignment.
+    pub fn to_raw_string(&self) -> String {
         unsafe {
             let s = dense_matrix_str(self.ptr);
             let result = CStr::from_ptr(s).to_string_lossy().into_owned();
@@ -547,6 +2638,239 @@ impl Matrix {
             result
         }
     }
+
+    /// The `Display` layout with every entry longer than `max_entry_width`
+    /// characters cut short and ending in `…`.
+    pub fn to_pretty_string(&self, max_entry_width: usize) -> String {
+        self.grid(Some(max_entry_width.max(1)))
+    }
+
+    fn grid(&self, max_width: Option<usize>) -> String {
+        let cells: Vec<Vec<String>> = self
+            .iter_rows()
+            .map(|row| {
+                row.iter()
+                    .map(|e| {
+                        let s = e.to_string();
+                        match max_width {
+                            Some(w) if s.chars().count() > w => {
+                                s.chars().take(w - 1).chain(std::iter::once('…')).collect()
+                            }
+                            _ => s,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut widths = vec![0; self.cols() as usize];
+        for row in &cells {
+            for (w, s) in widths.iter_mut().zip(row) {
+                *w = (*w).max(s.chars().count());
+            }
+        }
+        let lines: Vec<String> = cells
+            .iter()
+            .map(|row| {
+                let mut line = String::from("[");
+                for (c, s) in row.iter().enumerate() {
+                    line.push_str(s);
+                    if c + 1 < row.len() {
+                        line.push(',');
+                        let pad = widths[c] - s.chars().count() + 1;
+                        line.extend(std::iter::repeat_n(' ', pad));
+                    }
+                }
+                line.push(']');
+                line
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    /// `solve` for a single right-hand side given as a column vector.
+    pub fn solve_vec(&self, b: &[Expr]) -> Result<Vec<Expr>, MatrixError> {
+        let rhs = Self::from_entries(b.len() as u32, 1, b);
+        Ok(self.solve(&rhs)?.to_vec())
+    }
+
+    /// Eigenvalues as the roots of the characteristic polynomial
+    /// `det(self - λI)`, found with `solve_poly`. The roots come back as a
+    /// set, so a repeated eigenvalue is listed once. λ is a fresh symbol
+    /// whose name does not occur in any entry.
+    pub fn eigenvalues(&self) -> Result<Vec<Expr>, MatrixError> {
+        let n = self.rows();
+        if n != self.cols() {
+            return Err(MatrixError::NotSquare { rows: n, cols: self.cols() });
+        }
+        let entries = self.to_vec();
+        let used = self.free_symbols();
+        let name = std::iter::once("lambda".to_string())
+            .chain((1..).map(|i| format!("lambda_{}", i)))
+            .find(|s| !used.contains(s))
+            .unwrap();
+        let lambda = Expr::symbol(&name);
+        let shifted: Vec<Expr> = (0..n * n)
+            .zip(&entries)
+            .map(|(i, e)| if i / n == i % n { e.sub(&lambda) } else { e.clone() })
+            .collect();
+        let char_poly = Matrix::from_entries(n, n, &shifted).det().expand();
+        Ok(char_poly.solve_poly_set(&lambda).iter().collect())
+    }
+
+    /// Place `other` to the right of `self`, e.g. to build an augmented
+    /// matrix `[A | b]`. Both must have the same number of rows.
+    pub fn hstack(&self, other: &Matrix) -> Result<Matrix, MatrixError> {
+        if self.rows() != other.rows() {
+            return Err(self.shape_mismatch(other));
+        }
+        let r = self.clone();
+        unsafe { dense_matrix_row_join(r.ptr, other.ptr) };
+        Ok(r)
+    }
+
+    /// Place `other` below `self`. Both must have the same number of columns.
+    pub fn vstack(&self, other: &Matrix) -> Result<Matrix, MatrixError> {
+        if self.cols() != other.cols() {
+            return Err(self.shape_mismatch(other));
+        }
+        let r = self.clone();
+        unsafe { dense_matrix_col_join(r.ptr, other.ptr) };
+        Ok(r)
+    }
+
+    /// Copy of `self` without row `i`. Deleting the only row leaves a
+    /// 0×cols matrix.
+    pub fn delete_row(&self, i: u32) -> Result<Matrix, MatrixError> {
+        let (rows, cols) = (self.rows(), self.cols());
+        if i >= rows {
+            return Err(MatrixError::OutOfRange { index: i, len: rows });
+        }
+        if rows == 1 {
+            return Ok(unsafe { Self { ptr: dense_matrix_new_rows_cols(0, cols as _) } });
+        }
+        let r = self.clone();
+        unsafe { dense_matrix_row_del(r.ptr, i as _) };
+        Ok(r)
+    }
+
+    /// Copy of `self` without column `j`. Deleting the only column leaves a
+    /// rows×0 matrix.
+    pub fn delete_col(&self, j: u32) -> Result<Matrix, MatrixError> {
+        let (rows, cols) = (self.rows(), self.cols());
+        if j >= cols {
+            return Err(MatrixError::OutOfRange { index: j, len: cols });
+        }
+        if cols == 1 {
+            return Ok(unsafe { Self { ptr: dense_matrix_new_rows_cols(rows as _, 0) } });
+        }
+        let r = self.clone();
+        unsafe { dense_matrix_col_del(r.ptr, j as _) };
+        Ok(r)
+    }
+
+    fn shape_mismatch(&self, other: &Matrix) -> MatrixError {
+        MatrixError::ShapeMismatch {
+            left: (self.rows(), self.cols()),
+            right: (other.rows(), other.cols()),
+        }
+    }
+
+    /// Kronecker product: for p×r `self` and q×s `other`, the pq×rs block
+    /// matrix whose (i, j) block is `self[i][j] * other`.
+    pub fn kron(&self, other: &Matrix) -> Self {
+        let (p, r) = (self.rows(), self.cols());
+        let (q, s) = (other.rows(), other.cols());
+        let mut m = Self::zeros(p * q, r * s);
+        for i in 0..p {
+            for j in 0..r {
+                let a = self.get(i, j);
+                for k in 0..q {
+                    for l in 0..s {
+                        m.set(i * q + k, j * s + l, &a.mul(&other.get(k, l)));
+                    }
+                }
+            }
+        }
+        m
+    }
+
+    /// Entry-wise derivative with respect to `sym`, which must be a symbol.
+    /// Entries free of `sym` become exact zeros.
+    pub fn diff(&self, sym: &Expr) -> Self {
+        assert!(sym.is_symbol(), "can only differentiate with respect to a symbol");
+        unsafe {
+            let r = dense_matrix_new_rows_cols(self.rows() as _, self.cols() as _);
+            dense_matrix_diff(r, self.ptr, sym.as_ptr());
+            Self { ptr: r }
+        }
+    }
+
+    /// Jacobian of a column vector of functions (`self`, n×1) with respect to
+    /// a column vector of symbols (m×1). The result is n×m.
+    ///
+    /// Panics if either operand is not a column vector or an entry of
+    /// `symbols` is not a symbol.
+    pub fn jacobian(&self, symbols: &Matrix) -> Self {
+        assert!(
+            self.cols() == 1 && symbols.cols() == 1,
+            "jacobian needs column vectors, got {}x{} and {}x{}",
+            self.rows(),
+            self.cols(),
+            symbols.rows(),
+            symbols.cols()
+        );
+        assert!(
+            (0..symbols.rows()).all(|r| symbols.get(r, 0).is_symbol()),
+            "can only differentiate with respect to a symbol"
+        );
+        unsafe {
+            let r = dense_matrix_new_rows_cols(self.rows() as _, symbols.rows() as _);
+            dense_matrix_jacobian(r, self.ptr, symbols.ptr);
+            Self { ptr: r }
+        }
+    }
+}
+
+/// One line per row, e.g. for `[[x, 1], [10, y + 1]]`:
+///
+/// ```text
+/// [x,  1]
+/// [10, 1 + y]
+/// ```
+///
+/// Each column is padded to its widest entry.
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.grid(None))
+    }
+}
+
+impl fmt::Debug for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Matrix")
+            .field("rows", &self.rows())
+            .field("cols", &self.cols())
+            .field("value", &self.to_string())
+            .finish()
+    }
+}
+
+impl PartialEq for Matrix {
+    fn eq(&self, other: &Self) -> bool {
+        Matrix::eq(self, other)
+    }
+}
+
+impl Eq for Matrix {}
+
+impl Clone for Matrix {
+    fn clone(&self) -> Self {
+        unsafe {
+            let ptr = dense_matrix_new();
+            dense_matrix_set(ptr, self.ptr);
+            Self { ptr }
+        }
+    }
 }
 
 impl Drop for Matrix {
@@ -555,6 +2879,219 @@ impl Drop for Matrix {
     }
 }
 
+/// One Bareiss step: clear column `c` below row `r`, pivoting on `a[r][c]`.
+/// Each updated entry is divided exactly by `prev`, the previous step's
+/// pivot (1 for the first), so entries stay minors of the original matrix
+/// instead of doubling in degree at every step.
+fn bareiss_step(a: &mut [Vec<Expr>], r: usize, c: usize, prev: &Expr) {
+    let (top, rest) = a.split_at_mut(r + 1);
+    let pivot_row = &top[r];
+    for row in rest {
+        let factor = row[c].clone();
+        for (x, p) in row[c..].iter_mut().zip(&pivot_row[c..]) {
+            let cross = pivot_row[c].mul(x).sub(&factor.mul(p)).expand();
+            *x = exact_quotient(&cross, prev);
+        }
+    }
+}
+
+/// `num / den` where `den` is known to divide `num`: a polynomial when both
+/// are polynomials in the symbols of `den`, otherwise the plain quotient.
+fn exact_quotient(num: &Expr, den: &Expr) -> Expr {
+    poly_quotient(num, den).unwrap_or_else(|| num.div(den).expand())
+}
+
+/// Exact division by one symbol of `den` at a time, or `None` if either side
+/// is not a polynomial in it or a remainder is left.
+fn poly_quotient(num: &Expr, den: &Expr) -> Option<Expr> {
+    let Some(x) = den.free_symbol_exprs().into_iter().next() else {
+        return Some(num.div(den).expand());
+    };
+    let (dd, lead) = (den.degree(&x)?, den.leading_coeff(&x)?);
+    let mut rem = num.expand();
+    let mut quotient = Expr::zero();
+    while !rem.is_zero() {
+        let dr = rem.degree(&x)?;
+        if dr < dd {
+            return None;
+        }
+        let shift = Expr::from((dr - dd) as i64);
+        let term = poly_quotient(&rem.leading_coeff(&x)?, &lead)?.mul(&x.pow(&shift));
+        quotient = quotient.add(&term);
+        rem = rem.sub(&term.mul(den)).expand();
+        if !rem.is_zero() && rem.degree(&x)? >= dr {
+            return None;
+        }
+    }
+    Some(quotient.expand())
+}
+
+// =========================================================================
+// Sparse matrix wrapper
+// =========================================================================
+
+/// A matrix stored in SymEngine's compressed sparse row format, for large
+/// matrices that are mostly zero. The cwrapper only offers element access,
+/// so the shape and the positions that were ever set are tracked here;
+/// arithmetic goes through `to_dense`.
+pub struct SparseMatrix {
+    ptr: *mut CSparseMatrix,
+    rows: u32,
+    cols: u32,
+    positions: std::collections::BTreeSet<(u32, u32)>,
+}
+
+impl SparseMatrix {
+    /// rows × cols matrix with no stored entries (all zero).
+    pub fn new(rows: u32, cols: u32) -> Self {
+        unsafe {
+            let ptr = sparse_matrix_new();
+            sparse_matrix_rows_cols(ptr, rows as _, cols as _);
+            Self { ptr, rows, cols, positions: Default::default() }
+        }
+    }
+
+    /// Build from `(row, col, value)` triplets. Values at the same position
+    /// are added together, as when assembling a matrix from contributions.
+    pub fn from_triplets(
+        rows: u32,
+        cols: u32,
+        triplets: &[(u32, u32, Expr)],
+    ) -> Result<Self, MatrixError> {
+        let mut sums: std::collections::BTreeMap<(u32, u32), Expr> = Default::default();
+        for (r, c, value) in triplets {
+            check_index(*r, rows)?;
+            check_index(*c, cols)?;
+            match sums.get_mut(&(*r, *c)) {
+                Some(sum) => *sum += value,
+                None => {
+                    sums.insert((*r, *c), value.clone());
+                }
+            }
+        }
+        let mut m = Self::new(rows, cols);
+        for ((r, c), value) in &sums {
+            m.set(*r, *c, value);
+        }
+        Ok(m)
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn cols(&self) -> u32 {
+        self.cols
+    }
+
+    /// (rows, cols).
+    pub fn shape(&self) -> (u32, u32) {
+        (self.rows, self.cols)
+    }
+
+    /// The entry at row r, column c; zero if nothing is stored there.
+    ///
+    /// Panics if the position is outside the matrix.
+    pub fn get(&self, r: u32, c: u32) -> Expr {
+        self.try_get(r, c).unwrap_or_else(|_| {
+            panic!("position ({}, {}) outside {}x{} matrix", r, c, self.rows, self.cols)
+        })
+    }
+
+    /// Like `get`, but reports an out-of-range row (checked first) or
+    /// column as `OutOfRange`.
+    pub fn try_get(&self, r: u32, c: u32) -> Result<Expr, MatrixError> {
+        check_index(r, self.rows)?;
+        check_index(c, self.cols)?;
+        unsafe {
+            let e = basic_new_heap();
+            sparse_matrix_get_basic(e, self.ptr, r as _, c as _);
+            Ok(Expr { ptr: e })
+        }
+    }
+
+    /// Overwrite the entry at row r, column c. Setting zero removes the
+    /// stored entry.
+    ///
+    /// Panics if the position is outside the matrix.
+    pub fn set(&mut self, r: u32, c: u32, value: &Expr) {
+        assert!(
+            r < self.rows && c < self.cols,
+            "position ({}, {}) outside {}x{} matrix",
+            r,
+            c,
+            self.rows,
+            self.cols
+        );
+        unsafe { sparse_matrix_set_basic(self.ptr, r as _, c as _, value.as_ptr()) };
+        self.positions.insert((r, c));
+    }
+
+    /// Nonzero entries as `(row, col, value)`, in row-major order.
+    pub fn triplets(&self) -> Vec<(u32, u32, Expr)> {
+        self.positions
+            .iter()
+            .map(|&(r, c)| (r, c, self.get(r, c)))
+            .filter(|(_, _, e)| !e.is_zero())
+            .collect()
+    }
+
+    /// Number of nonzero entries.
+    pub fn nnz(&self) -> usize {
+        self.triplets().len()
+    }
+
+    /// Dense copy with the same entries.
+    pub fn to_dense(&self) -> Matrix {
+        let mut m = Matrix::zeros(self.rows, self.cols);
+        for (r, c, e) in self.triplets() {
+            m.set(r, c, &e);
+        }
+        m
+    }
+}
+
+fn check_index(index: u32, len: u32) -> Result<(), MatrixError> {
+    if index >= len {
+        return Err(MatrixError::OutOfRange { index, len });
+    }
+    Ok(())
+}
+
+impl PartialEq for SparseMatrix {
+    fn eq(&self, other: &Self) -> bool {
+        self.shape() == other.shape() && unsafe { sparse_matrix_eq(self.ptr, other.ptr) != 0 }
+    }
+}
+
+impl fmt::Display for SparseMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = unsafe {
+            let s = sparse_matrix_str(self.ptr);
+            let result = CStr::from_ptr(s).to_string_lossy().into_owned();
+            basic_str_free(s);
+            result
+        };
+        f.write_str(&text)
+    }
+}
+
+impl fmt::Debug for SparseMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SparseMatrix")
+            .field("rows", &self.rows)
+            .field("cols", &self.cols)
+            .field("nnz", &self.nnz())
+            .finish()
+    }
+}
+
+impl Drop for SparseMatrix {
+    fn drop(&mut self) {
+        unsafe { sparse_matrix_free(self.ptr) }
+    }
+}
+
 /// Return the SymEngine version string.
 pub fn version_str() -> String {
     unsafe {
@@ -562,3 +3099,1042 @@ pub fn version_str() -> String {
         CStr::from_ptr(s).to_string_lossy().into_owned()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    fn p(s: &str) -> Expr {
+        s.parse().unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn jacobian_of_column_vectors() {
+        let f = Matrix::from_vec(2, 1, &[p("x**2*y"), p("5*x + sin(y)")]).unwrap();
+        let v = Matrix::from_vec(2, 1, &[p("x"), p("y")]).unwrap();
+        let j = f.jacobian(&v);
+        assert_eq!((j.rows(), j.cols()), (2, 2));
+        assert_eq!(j.get(0, 0), p("2*x*y"));
+        assert_eq!(j.get(0, 1), p("x**2"));
+        assert_eq!(j.get(1, 0), p("5"));
+        assert_eq!(j.get(1, 1), p("cos(y)"));
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "jacobian needs column vectors, got 2x1 and 1x2")]
+    fn jacobian_rejects_row_vectors() {
+        let f = Matrix::from_vec(2, 1, &[p("x"), p("y")]).unwrap();
+        f.jacobian(&Matrix::from_vec(1, 2, &[p("x"), p("y")]).unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "can only differentiate with respect to a symbol")]
+    fn jacobian_rejects_non_symbols() {
+        let f = Matrix::from_vec(2, 1, &[p("x"), p("y")]).unwrap();
+        f.jacobian(&Matrix::from_vec(2, 1, &[p("x"), p("2*y")]).unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    fn taylor_series() {
+        let x = Expr::symbol("x");
+        let zero = Expr::zero();
+        // Removable singularity at the expansion point.
+        let sinc = p("sin(x)/x");
+        assert_eq!(sinc.taylor(&x, &zero, 4), p("1 - x**2/6 + x**4/120"));
+        assert_eq!(sinc.taylor(&x, &zero, 0), p("1"));
+        assert_eq!(p("(1 - cos(x))/x**2").taylor(&x, &zero, 2), p("1/2 - x**2/24"));
+        assert_eq!(p("exp(-x**2)").taylor(&x, &zero, 4), p("1 - x**2 + x**4/2"));
+        assert_eq!(p("cos(x)").taylor(&x, &zero, 0), p("1"));
+        // Symbolic expansion point.
+        let a = Expr::symbol("a");
+        assert_eq!(p("x**3").taylor(&x, &a, 3), p("x**3"));
+        assert_eq!(p("exp(x)").taylor(&x, &a, 1), p("exp(a) + x*exp(a) - a*exp(a)"));
+        assert_eq!(p("sin(x - a)/(x - a)").taylor(&x, &a, 0), p("1"));
+        // Poles are errors, not nan coefficients.
+        assert_eq!(p("1/x").try_taylor(&x, &zero, 2), Err(SymEngineError::DomainError));
+        assert_eq!(p("cos(x)/x").try_taylor(&x, &zero, 2), Err(SymEngineError::DomainError));
+        assert_eq!(p("log(x)").try_taylor(&x, &zero, 2), Err(SymEngineError::DomainError));
+        assert_eq!(p("x").try_taylor(&p("2*x"), &zero, 2), Err(SymEngineError::RuntimeError));
+    }
+
+    #[wasm_bindgen_test]
+    fn hash_matches_eq() {
+        use std::collections::HashSet;
+        let set: HashSet<Expr> = ["x + y", "y + x", "x*y", "y*x"].iter().map(|s| p(s)).collect();
+        assert_eq!(set.len(), 2);
+        assert_eq!(p("x + y").hash_value(), p("y + x").hash_value());
+    }
+
+    fn matrix(rows: &[&[&str]]) -> Matrix {
+        let rows: Vec<Vec<Expr>> = rows.iter().map(|r| r.iter().map(|s| p(s)).collect()).collect();
+        Matrix::from_nested(&rows).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn bareiss_elimination() {
+        assert_eq!(poly_quotient(&p("x**2 - y**2"), &p("x - y")), Some(p("x + y")));
+        assert_eq!(poly_quotient(&p("6*a*b**2 + 3*a"), &p("3*a")), Some(p("2*b**2 + 1")));
+        assert_eq!(poly_quotient(&p("x**2 + 1"), &p("x - 1")), None);
+        assert_eq!(poly_quotient(&p("sin(x)"), &p("x")), None);
+        assert_eq!(exact_quotient(&p("x**3 - 1"), &p("x - 1")), p("x**2 + x + 1"));
+        // The pivots are the leading principal minors, so the last one is
+        // the determinant itself.
+        let m = matrix(&[&["a", "b", "c"], &["d", "e", "f"], &["g", "h", "i"]]);
+        let mut a = m.expanded_rows();
+        bareiss_step(&mut a, 0, 0, &Expr::one());
+        let prev = a[0][0].clone();
+        bareiss_step(&mut a, 1, 1, &prev);
+        assert_eq!(a[1][1], p("a*e - b*d"));
+        assert_eq!(a[2][2], m.det().expand());
+        // Sizes stay at the minors' size, so a 6x6 symbolic matrix is quick.
+        // The diagonal carries the highest powers of x, so the determinant
+        // is nonzero.
+        let full = Matrix::from_fn(6, 6, |i, j| {
+            p(&format!("{}*x**{} + {}*y + {}", (i == j) as u32, i + 1, i + j, i * j))
+        });
+        assert_eq!(full.rank(), 6);
+        // u*v^T + w*z^T has rank 2 whatever the symbols are.
+        let low = Matrix::from_fn(6, 6, |i, j| p(&format!("(x + {i})*y**{j} + {i}*x*(z - {j})")));
+        assert_eq!(low.rank(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn max_abs_entry() {
+        assert_eq!(matrix(&[&["1", "-7/2"], &["3", "0"]]).max_abs_entry(), p("7/2"));
+        assert_eq!(matrix(&[&["3 + 4*I", "-2"]]).max_abs_entry(), p("5"));
+        assert_eq!(Matrix::zeros(0, 0).max_abs_entry(), Expr::zero());
+    }
+
+    #[wasm_bindgen_test]
+    fn fallible_operations() {
+        assert_eq!(SymEngineError::from_code(0), None);
+        assert_eq!(SymEngineError::from_code(2), Some(SymEngineError::DivByZero));
+        assert_eq!(SymEngineError::from_code(4), Some(SymEngineError::DomainError));
+        assert_eq!(SymEngineError::from_code(99), Some(SymEngineError::RuntimeError));
+        let x = Expr::symbol("x");
+        assert_eq!(x.try_div(&Expr::zero()), Err(SymEngineError::DivByZero));
+        assert_eq!(x.try_div(&Expr::integer(2)), Ok(p("x/2")));
+        assert_eq!(Expr::zero().try_pow(&Expr::minus_one()), Err(SymEngineError::DivByZero));
+        assert_eq!(Expr::zero().try_pow(&Expr::integer(2)), Ok(Expr::zero()));
+        assert_eq!(p("log(0)").try_evalf(53, true), Err(SymEngineError::DomainError));
+        assert_eq!(p("pi").try_evalf(200, true), Err(SymEngineError::NotImplemented));
+        assert_eq!(p("x + pi").try_evalf(53, true), Err(SymEngineError::SymbolicResult));
+        assert!(Expr::try_parse("x +* 2").is_err());
+        let singular = matrix(&[&["1", "2"], &["2", "4"]]);
+        assert_eq!(singular.try_inv(), Err(MatrixError::Singular));
+        let m = matrix(&[&["1", "2"], &["3", "4"]]);
+        assert_eq!(m.try_inv().unwrap().mul(&m), Matrix::identity(2));
+    }
+
+    #[wasm_bindgen_test]
+    fn arithmetic_operators() {
+        let x = Expr::symbol("x");
+        let one = Expr::one();
+        let product = (&x + &one) * (&x - &one);
+        assert!(product.expand().eq(&p("x**2 - 1")));
+        assert_eq!(x.clone() + &one, p("x + 1"));
+        assert_eq!(&x * x.clone(), p("x**2"));
+        assert_eq!(x.clone() / Expr::integer(2), p("x/2"));
+        assert_eq!(-&x, p("-x"));
+        assert_eq!(-(x.clone() - one.clone()), p("1 - x"));
+        let mut acc = Expr::zero();
+        for i in 1..=4 {
+            acc += Expr::integer(i) * &x;
+        }
+        assert_eq!(acc, p("10*x"));
+        let mut prod = one.clone();
+        prod *= &x;
+        prod *= x.clone();
+        assert_eq!(prod, p("x**2"));
+        // The right-hand operands are still usable afterwards.
+        assert_eq!(x, p("x"));
+        assert_eq!(one, p("1"));
+    }
+
+    #[wasm_bindgen_test]
+    fn display_and_debug() {
+        let e = p("x**2 + 1");
+        assert_eq!(format!("{}", e), e.to_string());
+        assert_eq!(format!("{}", p("2*y")), "2*y");
+        assert_eq!(format!("f = {}", p("x")), "f = x");
+        assert_eq!(format!("{:?}", p("x")), r#"Expr { type: "Symbol", value: "x" }"#);
+        assert!(format!("{:?}", e).contains(r#"type: "Add""#));
+        let m = matrix(&[&["1", "x"]]);
+        assert_eq!(format!("{}", m), m.to_string());
+        assert!(format!("{:?}", m).contains("Matrix"));
+    }
+
+    #[wasm_bindgen_test]
+    fn hash_map_keys() {
+        use std::collections::HashMap;
+        let mut cache: HashMap<Expr, u32> = HashMap::new();
+        cache.insert(p("x + 2*y"), 1);
+        cache.insert(p("sin(x)**2"), 2);
+        assert_eq!(cache.get(&p("2*y + x")), Some(&1));
+        assert_eq!(cache.get(&p("sin(x)**2")), Some(&2));
+        assert_eq!(cache.get(&p("x + y")), None);
+        *cache.entry(p("y*2 + x")).or_insert(0) += 10;
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache[&p("x + 2*y")], 11);
+        assert_eq!(p("x*y"), p("y*x"));
+        assert_ne!(p("x"), p("y"));
+    }
+
+    #[wasm_bindgen_test]
+    fn from_str() {
+        let e: Expr = "x**2 + 1".parse().unwrap();
+        assert_eq!(e, Expr::symbol("x").pow(&Expr::integer(2)).add(&Expr::one()));
+        assert_eq!(
+            "x +* 2".parse::<Expr>(),
+            Err(ParseError::Syntax { input: "x +* 2".to_string() })
+        );
+        assert_eq!(
+            "(x + 1".parse::<Expr>(),
+            Err(ParseError::Syntax { input: "(x + 1".to_string() })
+        );
+        assert_eq!("x\0y".parse::<Expr>(), Err(ParseError::NulByte { position: 1 }));
+        assert_eq!(Expr::try_from("y - 3"), Ok(p("y - 3")));
+        assert_eq!("2*x + 1".parse::<Expr>().map(|e| e.to_string()), Ok("1 + 2*x".to_string()));
+    }
+
+    #[wasm_bindgen_test]
+    fn primitive_conversions() {
+        assert_eq!(Expr::from(-7i32), p("-7"));
+        assert_eq!(Expr::from(3_000_000_000i64).to_string(), "3000000000");
+        assert_eq!(Expr::from(-(1i64 << 40)).to_string(), "-1099511627776");
+        assert_eq!(Expr::from((1u64 << 53) + 1).to_string(), "9007199254740993");
+        assert_eq!(Expr::from(u64::MAX).to_string(), "18446744073709551615");
+        assert_eq!(Expr::from(0.5f64), Expr::real_double(0.5));
+        assert_eq!(Expr::from(f64::NAN), Expr::nan());
+        assert_eq!(Expr::from(f64::INFINITY), Expr::infinity());
+        assert_eq!(Expr::from(f64::NEG_INFINITY), Expr::neg_infinity());
+        assert_eq!(Expr::from((6i64, -4i64)), p("-3/2"));
+        assert_eq!(Expr::from((1i64 << 40, 1i64 << 39)), p("2"));
+        assert_eq!(Expr::try_from("x/2"), Ok(p("x/2")));
+        assert!(Expr::try_from("").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn sums_and_products() {
+        let terms: Vec<Expr> = (1..=5).map(|i| p(&format!("{}*x**{}", i, i))).collect();
+        let pairwise = terms.iter().fold(Expr::zero(), |acc, t| acc.add(t));
+        assert_eq!(add_all(&terms), pairwise);
+        assert_eq!(terms.iter().sum::<Expr>(), pairwise);
+        assert_eq!(terms.clone().into_iter().sum::<Expr>(), pairwise);
+        let factors: Vec<Expr> = ["x", "y + 1", "2", "x"].iter().map(|s| p(s)).collect();
+        let pairwise = factors.iter().fold(Expr::one(), |acc, f| acc.mul(f));
+        assert_eq!(mul_all(&factors), pairwise);
+        assert_eq!(factors.iter().product::<Expr>(), pairwise);
+        assert_eq!(add_all(&[]), Expr::zero());
+        assert_eq!(mul_all(&[]), Expr::one());
+        assert_eq!(std::iter::empty::<Expr>().sum::<Expr>(), Expr::zero());
+        assert_eq!(std::iter::empty::<Expr>().product::<Expr>(), Expr::one());
+        // 1 + 2 + ... + 10000 in one call.
+        let big: Vec<Expr> = (1..=10_000).map(Expr::integer).collect();
+        assert_eq!(add_all(&big), Expr::integer(50_005_000));
+        let x = Expr::symbol("x");
+        let powers: Expr = (0..10_000).map(|i| x.pow(&Expr::integer(i))).sum();
+        assert_eq!(powers.num_args(), 10_000);
+    }
+
+    #[wasm_bindgen_test]
+    fn exact_integers() {
+        assert_eq!(Expr::integer_i64(i64::MAX).to_string(), "9223372036854775807");
+        assert_eq!(Expr::integer_i64(i64::MIN).to_string(), "-9223372036854775808");
+        assert_eq!(Expr::integer_u64(u64::MAX).to_string(), "18446744073709551615");
+        for v in [(1i64 << 31) - 1, 1 << 31, (1 << 31) + 1, -(1 << 31), -(1 << 31) - 1] {
+            assert_eq!(Expr::integer_i64(v).to_string(), v.to_string());
+        }
+        assert_eq!(Expr::integer_i64(1 << 40), p("2**40"));
+        let digits = "1234567890".repeat(10);
+        let big = Expr::try_integer_from_str(&digits).unwrap();
+        assert_eq!(big.to_string(), digits);
+        assert!(big.is_integer());
+        assert_eq!(big.add(&Expr::one()).sub(&big), Expr::one());
+        assert_eq!(Expr::try_integer_from_str("-42"), Ok(Expr::integer(-42)));
+        for bad in ["", "-", "12a", "1.5", " 7", "+3"] {
+            assert!(Expr::try_integer_from_str(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn complex_numbers() {
+        let n = |i: i32| Expr::integer(i);
+        let z = Expr::complex(&n(3), &n(4));
+        assert_eq!(z, p("3 + 4*I"));
+        assert!(z.is_complex_type());
+        assert_eq!(z.abs(), n(5));
+        assert_eq!(Expr::complex(&n(3), &n(0)), n(3));
+        assert_eq!(Expr::complex(&n(0), &n(-2)), p("-2*I"));
+        assert_eq!(Expr::complex(&n(1), &n(-1)), p("1 - I"));
+        let x = Expr::symbol("x");
+        assert_eq!(Expr::complex(&x, &n(2)), p("x + 2*I"));
+        assert_eq!(Expr::complex_double(1.5, -2.0).as_real_imag(), Some((1.5, -2.0)));
+    }
+
+    #[wasm_bindgen_test]
+    fn numeric_extraction() {
+        assert_eq!(Expr::integer_i64(i64::MAX).to_i64(), Some(i64::MAX));
+        assert_eq!(Expr::integer_i64(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!(Expr::integer_u64(i64::MAX as u64 + 1).to_i64(), None);
+        assert_eq!(p("-12").to_i64(), Some(-12));
+        let half = Expr::rational(1, 2);
+        assert_eq!(half.to_i64(), None);
+        assert_eq!(half.to_f64(), Some(0.5));
+        assert_eq!(Expr::pi().to_i64(), None);
+        assert_eq!(Expr::pi().to_f64(), Some(std::f64::consts::PI));
+        assert_eq!(Expr::real_double(2.25).to_f64(), Some(2.25));
+        assert_eq!(Expr::real_double(2.0).to_i64(), None);
+        assert_eq!(p("3 + 4*I").to_f64(), None);
+        assert_eq!(p("x + 1").to_f64(), None);
+        assert_eq!(p("x").to_i64(), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn expression_vectors() {
+        let exprs: Vec<Expr> = ["x", "y**2", "sin(z)", "3/4"].iter().map(|s| p(s)).collect();
+        let vec = VecExpr::from(exprs.as_slice());
+        assert_eq!(vec.len(), 4);
+        assert_eq!(vec.get(2), p("sin(z)"));
+        assert_eq!(vec.iter().collect::<Vec<_>>(), exprs);
+        // Stopping an iteration early and dropping the vector is fine.
+        let mut it = vec.iter();
+        assert_eq!(it.next(), Some(p("x")));
+        drop(it);
+        let back: Vec<Expr> = vec.into();
+        assert_eq!(back, exprs);
+        let mut empty = VecExpr::new();
+        assert!(empty.is_empty());
+        empty.push(&exprs[0]);
+        drop(exprs);
+        assert_eq!(empty.get(0), p("x"));
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "index 1 out of bounds for VecExpr of length 1")]
+    fn vector_index_out_of_bounds() {
+        let vec = VecExpr::from(&[p("x")][..]);
+        vec.get(1);
+    }
+
+    #[wasm_bindgen_test]
+    fn expression_sets() {
+        let mut set = ExprSet::new();
+        assert!(set.insert(&p("x + 1")));
+        assert!(set.insert(&p("y")));
+        assert!(!set.insert(&p("1 + x")));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&p("x + 1")));
+        assert!(!set.contains(&p("x")));
+        assert_eq!(set.iter().count(), 2);
+        let mut syms = p("x*y + sin(z) + x").free_symbols();
+        syms.sort();
+        assert_eq!(syms, ["x", "y", "z"]);
+        assert!(p("2 + pi").free_symbols().is_empty());
+        let e = p("a*b + c");
+        let vars = e.free_symbol_exprs();
+        assert_eq!(vars.len(), 3);
+        let pairs: Vec<(&Expr, &Expr)> = vars.iter().map(|v| (v, v)).collect();
+        assert_eq!(e.subs_map(&pairs), e);
+        let mut roots = p("x**2 - 4").solve_poly(&Expr::symbol("x"));
+        roots.sort();
+        assert_eq!(roots, ["-2", "2"]);
+        assert_eq!(p("x - 5").solve_poly(&Expr::symbol("x")), ["5"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn reusable_substitution_table() {
+        let mut map = SubsMap::new();
+        assert!(map.is_empty());
+        {
+            let (a, b) = (Expr::symbol("a"), Expr::symbol("b"));
+            map.insert(&a, &Expr::integer(2));
+            map.insert(&b, &p("c + 1"));
+            map.insert(&a, &Expr::integer(3));
+        }
+        assert_eq!(map.len(), 2);
+        let results: Vec<Expr> = (0..200)
+            .map(|i| p(&format!("a*x**{} + b", i)).subs_with(&map))
+            .collect();
+        assert_eq!(results[0], p("4 + c"));
+        assert_eq!(results[199], p("3*x**199 + c + 1"));
+        // The results own their nodes; dropping the map first is fine.
+        drop(map);
+        assert_eq!(results[1].to_string(), p("1 + c + 3*x").to_string());
+    }
+
+    #[wasm_bindgen_test]
+    fn multiple_symbols() {
+        let names = |s: &str| -> Result<Vec<String>, SymbolsError> {
+            Expr::symbols(s).map(|v| v.iter().map(Expr::to_string).collect())
+        };
+        assert_eq!(names("x y z").unwrap(), ["x", "y", "z"]);
+        assert_eq!(names("a, b,c").unwrap(), ["a", "b", "c"]);
+        assert_eq!(names("x0:3").unwrap(), ["x0", "x1", "x2"]);
+        assert_eq!(names("q2:4 t").unwrap(), ["q2", "q3", "t"]);
+        assert_eq!(names("v:2").unwrap(), ["v0", "v1"]);
+        assert_eq!(names("s0:20").unwrap().len(), 20);
+        assert_eq!(names("x y x"), Err(SymbolsError::Duplicate("x".to_string())));
+        assert_eq!(names("x0:2 x1"), Err(SymbolsError::Duplicate("x1".to_string())));
+        assert_eq!(names("x+y"), Err(SymbolsError::InvalidName("x+y".to_string())));
+        assert_eq!(names("2x"), Err(SymbolsError::InvalidName("2x".to_string())));
+        assert_eq!(names("x3:1"), Err(SymbolsError::InvalidRange("x3:1".to_string())));
+        assert_eq!(names("x:y"), Err(SymbolsError::InvalidRange("x:y".to_string())));
+        assert_eq!(names(" , "), Err(SymbolsError::Empty));
+    }
+
+    #[wasm_bindgen_test]
+    fn undefined_functions() {
+        let x = Expr::symbol("x");
+        let y = Expr::symbol("y");
+        let f = Expr::function("f", std::slice::from_ref(&x));
+        assert_eq!(f.to_string(), "f(x)");
+        assert_eq!(f.function_name(), Some("f".to_string()));
+        assert_eq!(f.type_kind(), TypeKind::FunctionSymbol);
+        assert_eq!(p("sin(x)").function_name(), None);
+        let g = Expr::function("g", &[x.clone(), y.clone()]);
+        assert_eq!(g.to_string(), "g(x, y)");
+        assert_eq!(g.args(), [x.clone(), y.clone()]);
+        let df = f.diff(&x);
+        assert_eq!(df.type_kind(), TypeKind::Derivative);
+        assert!(df.to_string().contains("f(x)"));
+        assert!(g.diff(&Expr::symbol("z")).is_zero());
+        let h = f.mul(&x).add(&Expr::one());
+        assert_eq!(h.subs(&f, &p("sin(x)")), p("x*sin(x) + 1"));
+        assert_eq!(f.subs(&x, &Expr::integer(2)).to_string(), "f(2)");
+        assert_eq!(Expr::try_function("f", std::slice::from_ref(&x)), Ok(f));
+        for bad in ["", "2f", "f(x)", "a b"] {
+            assert_eq!(Expr::try_function(bad, &[]), Err(SymbolsError::InvalidName(bad.to_string())));
+        }
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "invalid function name \"f-g\"")]
+    fn function_panics_on_bad_name() {
+        Expr::function("f-g", &[Expr::symbol("x")]);
+    }
+
+    /// Rebuild an expression bottom-up from `type_kind` and `args`.
+    fn rebuild(e: &Expr) -> Expr {
+        let args: Vec<Expr> = e.args().iter().map(rebuild).collect();
+        match e.type_kind() {
+            TypeKind::Add => add_all(&args),
+            TypeKind::Mul => mul_all(&args),
+            TypeKind::Pow => args[0].pow(&args[1]),
+            TypeKind::Sin => args[0].sin(),
+            TypeKind::Cos => args[0].cos(),
+            TypeKind::Log => args[0].log(),
+            TypeKind::FunctionSymbol => Expr::function(&e.function_name().unwrap(), &args),
+            _ => {
+                assert!(args.is_empty(), "unexpected node {:?}", e);
+                e.clone()
+            }
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn child_expressions() {
+        assert_eq!(p("x + y + z").num_args(), 3);
+        assert_eq!(p("2*x*y").num_args(), 3);
+        assert_eq!(p("x**y").args(), [p("x"), p("y")]);
+        assert_eq!(p("x").args(), []);
+        assert_eq!(p("7").num_args(), 0);
+        assert_eq!(p("pi").num_args(), 0);
+        assert_eq!(p("sin(x + 1)").args(), [p("x + 1")]);
+        let g = Expr::function("g", &[p("x"), p("cos(y)")]);
+        let args = g.args();
+        assert_eq!(args, [p("x"), p("cos(y)")]);
+        assert_eq!(args[1].args(), [p("y")]);
+        for s in ["x**2*sin(y) + log(x + 1)/3 - 7", "(a + b)**(c*d)", "cos(sin(x))**2"] {
+            let e = p(s);
+            assert_eq!(rebuild(&e), e, "{}", s);
+        }
+        let h = Expr::function("h", &[p("x**2 + 1")]).mul(&p("y"));
+        assert_eq!(rebuild(&h), h);
+    }
+
+    #[wasm_bindgen_test]
+    fn type_introspection() {
+        let f = Expr::function("f", &[p("x")]);
+        let cases = [
+            (p("3"), TypeKind::Integer, "Integer"),
+            (p("3/4"), TypeKind::Rational, "Rational"),
+            (p("1 + 2*I"), TypeKind::Complex, "Complex"),
+            (Expr::real_double(1.5), TypeKind::RealDouble, "RealDouble"),
+            (Expr::pi(), TypeKind::Constant, "Constant"),
+            (Expr::infinity(), TypeKind::Infinity, "Infty"),
+            (Expr::nan(), TypeKind::NaN, "NaN"),
+            (p("x"), TypeKind::Symbol, "Symbol"),
+            (p("x + 1"), TypeKind::Add, "Add"),
+            (p("2*x"), TypeKind::Mul, "Mul"),
+            (p("x**2"), TypeKind::Pow, "Pow"),
+            (p("E**x"), TypeKind::Pow, "Pow"),
+            (p("sin(x)"), TypeKind::Sin, "Sin"),
+            (p("log(x)"), TypeKind::Log, "Log"),
+            (p("abs(x)"), TypeKind::Abs, "Abs"),
+            (f.clone(), TypeKind::FunctionSymbol, "FunctionSymbol"),
+            (f.diff(&p("x")), TypeKind::Derivative, "Derivative"),
+        ];
+        for (e, kind, name) in &cases {
+            assert_eq!(e.type_kind(), *kind, "{}", e);
+            assert_eq!(e.type_name(), *name, "{}", e);
+        }
+        assert_eq!(p("x").type_code(), p("y").type_code());
+        assert_ne!(p("x").type_code(), p("x + 1").type_code());
+        let gamma = p("gamma(x)");
+        assert_eq!(gamma.type_kind(), TypeKind::Other(gamma.type_code()));
+        assert!(p("1 + 2*I").is_complex_type());
+        assert!(!Expr::complex_double(1.0, 2.0).is_complex_type());
+        assert!(Expr::real_double(0.5).is_real_double());
+        assert!(!p("1/2").is_real_double());
+    }
+
+    /// Substitute the cse replacements back, last first.
+    fn undo_cse(replacements: &[(Expr, Expr)], e: &Expr) -> Expr {
+        replacements.iter().rev().fold(e.clone(), |acc, (s, sub)| acc.subs(s, sub))
+    }
+
+    #[wasm_bindgen_test]
+    fn common_subexpressions() {
+        let exprs = [p("sin(a)**2 + sin(a)"), p("cos(sin(a)) + b")];
+        let (replacements, reduced) = cse(&exprs);
+        assert!(!replacements.is_empty());
+        assert!(replacements.iter().any(|(_, e)| *e == p("sin(a)")));
+        assert_eq!(reduced.len(), 2);
+        for (orig, r) in exprs.iter().zip(&reduced) {
+            assert_eq!(undo_cse(&replacements, r).expand(), orig.expand());
+        }
+        let single = [p("(x + y)**2 + exp(x + y)")];
+        let (replacements, reduced) = cse(&single);
+        assert_eq!(replacements.len(), 1);
+        assert_eq!(replacements[0].1, p("x + y"));
+        assert_eq!(undo_cse(&replacements, &reduced[0]), single[0]);
+        let plain = [p("x + y"), p("a*b")];
+        let (replacements, reduced) = cse(&plain);
+        assert!(replacements.is_empty());
+        assert_eq!(reduced, plain);
+    }
+
+    #[wasm_bindgen_test]
+    fn fallible_parse() {
+        let still_works = || assert_eq!(Expr::parse("(x + 1)**2").expand(), p("x**2 + 2*x + 1"));
+        assert_eq!(Expr::try_parse("\0"), Err(ParseError::NulByte { position: 0 }));
+        still_works();
+        assert_eq!(Expr::try_parse("x + \0"), Err(ParseError::NulByte { position: 4 }));
+        still_works();
+        assert_eq!(Expr::try_parse(""), Err(ParseError::Empty));
+        still_works();
+        assert_eq!(Expr::try_parse("   "), Err(ParseError::Empty));
+        still_works();
+        assert_eq!(Expr::try_parse("\t\n"), Err(ParseError::Empty));
+        assert_eq!(
+            Expr::try_parse("x +* 2"),
+            Err(ParseError::Syntax { input: "x +* 2".to_string() })
+        );
+        still_works();
+        for bad in ["(x]", "[x)", "[x]", "f(x,)", "(x, y)", "x y", "2(x)", "1.2.3", "x^", "-", "a = b", "x % 2"] {
+            assert_eq!(Expr::try_parse(bad), Err(ParseError::Syntax { input: bad.to_string() }), "{:?}", bad);
+        }
+        still_works();
+        for good in ["-x**-2", "2x + 1e-3y", ".5 + 1.", "(x < 1) & ~(y >= 2)", "f (x, g(y))", "x^2 <= 3 | x == y", "αβ"] {
+            assert!(Expr::try_parse(good).is_ok(), "{:?}", good);
+        }
+        assert_eq!(Expr::parse_with_options("", true), Err(ParseError::Empty));
+        assert_eq!(Expr::parse_with_options("a\0", false), Err(ParseError::NulByte { position: 1 }));
+        assert_eq!(Expr::try_parse(" x + 1 "), Ok(p("x + 1")));
+        assert_eq!(Expr::parse_with_options("x^2", true), Ok(p("x**2")));
+        assert_eq!(Expr::try_symbol(""), Err(ParseError::Empty));
+        assert_eq!(Expr::try_symbol("a\0"), Err(ParseError::NulByte { position: 1 }));
+        assert_eq!(ParseError::NulByte { position: 3 }.to_string(), "expression contains a NUL byte at offset 3");
+        still_works();
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "expression contains a NUL byte at offset 1")]
+    fn parse_panics_on_nul() {
+        Expr::parse("x\0");
+    }
+
+    #[wasm_bindgen_test]
+    fn evalf_domain_errors() {
+        for s in ["log(0)", "gamma(-3)", "zeta(1)", "1/0", "0/0"] {
+            assert_eq!(p(s).try_evalf(53, false), Err(SymEngineError::DomainError), "{}", s);
+        }
+        assert_eq!(p("x + sin(1)").try_evalf(53, false), Err(SymEngineError::SymbolicResult));
+        assert_eq!(p("f(y)").try_evalf(53, true), Err(SymEngineError::SymbolicResult));
+        let v = p("log(2) + zeta(2)").try_evalf(53, true).unwrap();
+        assert!(v.is_real_double());
+        let expected = 2f64.ln() + std::f64::consts::PI.powi(2) / 6.0;
+        assert!((v.to_f64().unwrap() - expected).abs() < 1e-12);
+    }
+
+    /// Clone, print and drop shared expressions from 8 threads at once. Other
+    /// tests in this binary use SymEngine without the lock, so run it alone.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    #[ignore = "run alone: cargo test sync_expr_stress -- --ignored"]
+    fn sync_expr_stress() {
+        // SAFETY: with the test run alone, the scoped threads below are the
+        // only ones running, and they reach SymEngine only through `with`.
+        let shared: Vec<SyncExpr> = ["x**2 + 2*x + 1", "sin(y)*cos(y)", "3/7"]
+            .iter()
+            .map(|s| unsafe { SyncExpr::new(p(s)) })
+            .collect();
+        let expected: Vec<String> = shared.iter().map(|e| e.to_string()).collect();
+        std::thread::scope(|scope| {
+            for t in 0..8 {
+                let (shared, expected) = (&shared, &expected);
+                scope.spawn(move || {
+                    for i in 0..500 {
+                        let k = (t + i) % shared.len();
+                        let printed = shared[k].with(|e| {
+                            let copy = e.clone();
+                            let s = copy.to_string();
+                            drop(copy);
+                            s
+                        });
+                        assert_eq!(printed, expected[k]);
+                        assert_eq!(shared[k].to_string(), expected[k]);
+                    }
+                });
+            }
+        });
+        let back: Vec<Expr> = shared.into_iter().map(SyncExpr::into_inner).collect();
+        assert_eq!(back[2], p("3/7"));
+    }
+
+    #[cfg(all(feature = "thread-safe", not(target_arch = "wasm32")))]
+    #[test]
+    fn shared_expr_stress() {
+        let shared = p("(x + y)**3 + sin(x)");
+        let expected = shared.to_string();
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..500 {
+                        let copy = shared.clone();
+                        assert_eq!(copy.to_string(), expected);
+                        drop(copy);
+                    }
+                });
+            }
+        });
+        assert_eq!(shared.to_string(), expected);
+    }
+
+    #[wasm_bindgen_test]
+    fn repeated_and_mixed_derivatives() {
+        let (x, y) = (Expr::symbol("x"), Expr::symbol("y"));
+        let e = p("x**4*y**3 + sin(x*y)");
+        assert_eq!(e.diff_n(&x, 0), e);
+        assert_eq!(e.diff_n(&x, 2), e.diff(&x).diff(&x));
+        assert_eq!(p("x**3").diff_n(&x, 3), p("6"));
+        assert!(p("x**3").diff_n(&x, 4).is_zero());
+        let xxy = e.diff_multi(&[(&x, 2), (&y, 1)]).unwrap();
+        let yxx = e.diff_multi(&[(&y, 1), (&x, 2)]).unwrap();
+        let xyx = e.diff_multi(&[(&x, 1), (&y, 1), (&x, 1)]).unwrap();
+        assert_eq!(xxy.expand(), yxx.expand());
+        assert_eq!(xxy.expand(), xyx.expand());
+        assert_eq!(xxy.expand(), e.diff(&x).diff(&x).diff(&y).expand());
+        // Fourth-order mixed partial of a polynomial is exact.
+        let q = p("x**3*y**2");
+        assert_eq!(q.diff_multi(&[(&x, 2), (&y, 2)]), Ok(p("12*x")));
+        assert_eq!(q.diff_multi(&[]), Ok(q.clone()));
+        assert_eq!(q.diff_multi(&[(&x, 0)]), Ok(q.clone()));
+        let two_x = p("2*x");
+        assert_eq!(q.diff_multi(&[(&x, 1), (&two_x, 1)]), Err(SymEngineError::RuntimeError));
+        assert_eq!(q.try_diff(&two_x), Err(SymEngineError::RuntimeError));
+    }
+
+    #[wasm_bindgen_test]
+    fn newton_roots() {
+        let x = Expr::symbol("x");
+        let close = |a: f64, b: f64| assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+        // The Dottie number, cos(x) = x.
+        close(newton_solve(&p("x - cos(x)"), &x, 1.0, 1e-14, 50).unwrap(), 0.7390851332151607);
+        close(newton_solve(&p("x**2 - 2"), &x, 1.0, 1e-14, 50).unwrap(), std::f64::consts::SQRT_2);
+        close(newton_solve(&p("x**2 - 2"), &x, -1.0, 1e-14, 50).unwrap(), -std::f64::consts::SQRT_2);
+        close(newton_solve(&p("exp(x) - 3"), &x, 0.0, 1e-14, 50).unwrap(), 3f64.ln());
+        assert_eq!(newton_solve(&p("x - 2"), &x, 2.0, 1e-14, 50), Ok(2.0));
+        assert_eq!(
+            newton_solve(&p("x**2 + 1"), &x, 0.0, 1e-14, 50),
+            Err(SolveError::ZeroDerivative { iterations: 0 })
+        );
+        assert_eq!(
+            newton_solve(&p("x**2 + 1"), &x, 0.5, 1e-14, 20),
+            Err(SolveError::NoConvergence { iterations: 20 })
+        );
+        assert_eq!(
+            newton_solve(&p("sqrt(x) - 1"), &x, -4.0, 1e-14, 20),
+            Err(SolveError::NotReal { iterations: 0 })
+        );
+        assert_eq!(
+            newton_solve(&p("x*y - 1"), &x, 1.0, 1e-14, 20),
+            Err(SolveError::FreeSymbols(vec!["y".to_string()]))
+        );
+        match newton_solve(&p("a*x + b - cos(x)"), &x, 1.0, 1e-14, 20) {
+            Err(SolveError::FreeSymbols(mut names)) => {
+                names.sort();
+                assert_eq!(names, ["a", "b"]);
+            }
+            other => panic!("expected FreeSymbols, got {:?}", other),
+        }
+        assert_eq!(newton_solve(&p("x"), &p("2*x"), 1.0, 1e-14, 20), Err(SolveError::NotSymbol));
+    }
+
+    #[wasm_bindgen_test]
+    fn polynomial_inspection() {
+        let x = Expr::symbol("x");
+        let factored = p("(x - 1)*(x + 2)*(2*x + 3)");
+        assert_eq!(factored.degree(&x), Some(3));
+        assert_eq!(factored.coeffs(&x), Some(vec![p("-6"), p("-1"), p("5"), p("2")]));
+        assert_eq!(factored.leading_coeff(&x), Some(p("2")));
+        let symbolic = p("a*x**2 + b*x + c + x*b");
+        assert_eq!(symbolic.coeffs(&x), Some(vec![p("c"), p("2*b"), p("a")]));
+        assert_eq!(symbolic.leading_coeff(&x), Some(p("a")));
+        assert_eq!(p("7").degree(&x), Some(0));
+        assert_eq!(p("7").coeffs(&x), Some(vec![p("7")]));
+        assert_eq!(p("y**5").degree(&x), Some(0));
+        assert_eq!(p("(x**2 + 1)**3").degree(&x), Some(6));
+        assert_eq!(p("x**2*y + x*y**4").degree(&x), Some(2));
+        // Cancellation in the expansion lowers the degree.
+        assert_eq!(p("(x + 1)**2 - x**2").degree(&x), Some(1));
+        for s in ["sin(x)", "1/x", "x**(1/2)", "x**2 + exp(x)", "x**y"] {
+            assert_eq!(p(s).degree(&x), None, "{}", s);
+            assert_eq!(p(s).coeffs(&x), None, "{}", s);
+            assert_eq!(p(s).leading_coeff(&x), None, "{}", s);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn numeric_substitution() {
+        let (x, y) = (Expr::symbol("x"), Expr::symbol("y"));
+        let e = p("x**2*y + sin(x) - exp(y)/x");
+        let (xv, yv) = (1.5f64, -2.0f64);
+        let direct = xv * xv * yv + xv.sin() - yv.exp() / xv;
+        let v = e.eval_at(&[(&x, xv), (&y, yv)]).unwrap();
+        assert!((v - direct).abs() < 1e-12, "{} != {}", v, direct);
+        let s = e.subs_f64(&[(&x, xv), (&y, yv)]);
+        assert!(s.is_real_double());
+        assert_eq!(s.to_f64(), Some(v));
+        // Simultaneous: y's value is not substituted into x's.
+        assert_eq!(p("x + y").subs_f64(&[(&x, 1.0)]), p("1.0 + y"));
+        assert_eq!(e.eval_at(&[(&x, 1.0)]), Err(SymEngineError::SymbolicResult));
+        assert_eq!(p("sqrt(x)").eval_at(&[(&x, -1.0)]), Err(SymEngineError::DomainError));
+        assert!(e.eval_at(&[(&x, f64::NAN), (&y, 0.0)]).unwrap().is_nan());
+        assert!(p("x - x + y").eval_at(&[(&y, f64::NAN)]).unwrap().is_nan());
+        assert_eq!(p("7").eval_at(&[]), Ok(7.0));
+    }
+
+    #[wasm_bindgen_test]
+    fn rust_matrix_constructors() {
+        let hilbert = Matrix::from_fn(5, 5, |r, c| Expr::rational(1, (r + c + 1) as i32));
+        assert_eq!(hilbert.get(4, 4), p("1/9"));
+        let inv = hilbert.inv();
+        assert!(inv.to_vec().iter().all(Expr::is_integer));
+        assert_eq!(inv.get(0, 0), p("25"));
+        assert_eq!(inv.get(4, 4), p("44100"));
+        assert_eq!(hilbert.mul(&inv), Matrix::identity(5));
+        let a = matrix(&[&["a", "b", "c"], &["d", "e", "f"]]);
+        assert_eq!(Matrix::identity(2).mul(&a), a);
+        assert_eq!(a.mul(&Matrix::identity(3)), a);
+        let mut calls = Vec::new();
+        let m = Matrix::from_fn(2, 3, |r, c| {
+            calls.push((r, c));
+            Expr::integer((10 * r + c) as i32)
+        });
+        assert_eq!(calls, [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]);
+        assert_eq!(m, matrix(&[&["0", "1", "2"], &["10", "11", "12"]]));
+        assert_eq!(Matrix::zeros(2, 3).to_vec(), vec![Expr::zero(); 6]);
+        assert_eq!(Matrix::ones(3, 1).to_vec(), vec![Expr::one(); 3]);
+        assert_eq!(Matrix::zeros(1, 4).shape(), (1, 4));
+        let d = Matrix::diag(&[p("x"), p("y + 1")]);
+        assert_eq!(d, matrix(&[&["x", "0"], &["0", "y + 1"]]));
+        assert_eq!(Matrix::identity(3), Matrix::diag(&[Expr::one(), Expr::one(), Expr::one()]));
+    }
+
+    #[wasm_bindgen_test]
+    fn validated_matrix_construction() {
+        let e: Vec<Expr> = ["a", "b", "c", "d", "e", "f"].iter().map(|s| p(s)).collect();
+        let m = Matrix::from_vec(2, 3, &e).unwrap();
+        assert_eq!((m.get(0, 2), m.get(1, 0)), (p("c"), p("d")));
+        assert_eq!(m.to_vec(), e);
+        let t = Matrix::from_vec(3, 2, &e).unwrap();
+        assert_eq!((t.get(0, 1), t.get(2, 0)), (p("b"), p("e")));
+        assert_eq!(
+            Matrix::from_vec(2, 2, &e).err(),
+            Some(MatrixError::ElementCount { rows: 2, cols: 2, len: 6 })
+        );
+        assert_eq!(
+            Matrix::from_vec(3, 3, &e).err().unwrap().to_string(),
+            "expected 9 elements for a 3x3 matrix, got 6"
+        );
+        // rows × cols overflows u32 but not the check.
+        assert_eq!(
+            Matrix::from_vec(1 << 16, 1 << 16, &e).err(),
+            Some(MatrixError::ElementCount { rows: 1 << 16, cols: 1 << 16, len: 6 })
+        );
+        let rows = vec![e[..3].to_vec(), e[3..].to_vec()];
+        assert_eq!(Matrix::from_nested(&rows).unwrap(), m);
+        let ragged = vec![e[..3].to_vec(), e[3..5].to_vec()];
+        assert_eq!(
+            Matrix::from_nested(&ragged).err(),
+            Some(MatrixError::Ragged { row: 1, len: 2, expected: 3 })
+        );
+        assert_eq!(Matrix::from_nested(&[]).unwrap().shape(), (0, 0));
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "element count for a 2x2 matrix")]
+    fn from_entries_rejects_wrong_length() {
+        Matrix::from_entries(2, 2, &[p("1"), p("2"), p("3")]);
+    }
+
+    #[wasm_bindgen_test]
+    fn checked_entry_access() {
+        let mut m = matrix(&[&["a", "b", "c"], &["d", "e", "f"]]);
+        assert_eq!(m.shape(), (2, 3));
+        assert_eq!(m.transpose().shape(), (3, 2));
+        assert_eq!(m.transpose().get(2, 1), p("f"));
+        assert_eq!(m.try_get(1, 2), Ok(p("f")));
+        assert_eq!(m.try_get(2, 0), Err(MatrixError::OutOfRange { index: 2, len: 2 }));
+        assert_eq!(m.try_get(0, 3), Err(MatrixError::OutOfRange { index: 3, len: 3 }));
+        assert_eq!(m.try_get(9, 9), Err(MatrixError::OutOfRange { index: 9, len: 2 }));
+        m.set(1, 1, &p("x**2"));
+        m.set(0, 2, &p("0"));
+        assert_eq!(m.get(1, 1), p("x**2"));
+        assert_eq!(m, matrix(&[&["a", "b", "0"], &["d", "x**2", "f"]]));
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "position (2, 0) outside 2x3 matrix")]
+    fn get_out_of_range_panics() {
+        matrix(&[&["a", "b", "c"], &["d", "e", "f"]]).get(2, 0);
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "position (0, 3) outside 2x3 matrix")]
+    fn set_out_of_range_panics() {
+        matrix(&[&["a", "b", "c"], &["d", "e", "f"]]).set(0, 3, &p("x"));
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_iterators() {
+        let m = matrix(&[&["x", "y + 1", "2"], &["z*x", "0", "y"]]);
+        let elements: Vec<_> = m.iter_elements().collect();
+        assert_eq!(elements.len(), 6);
+        let positions: Vec<_> = elements.iter().map(|(rc, _)| *rc).collect();
+        assert_eq!(positions, [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]);
+        assert_eq!(elements[3].1, p("z*x"));
+        let flat = m.to_vec();
+        assert_eq!(flat, elements.into_iter().map(|(_, e)| e).collect::<Vec<_>>());
+        let rows: Vec<Vec<Expr>> = m.iter_rows().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], [p("z*x"), p("0"), p("y")]);
+        assert_eq!(rows.concat(), flat);
+        assert_eq!(m.free_symbols(), ["x", "y", "z"]);
+        assert!(matrix(&[&["1", "pi"]]).free_symbols().is_empty());
+        assert_eq!(Matrix::zeros(0, 0).iter_elements().count(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn lu_solve_residuals() {
+        let residual_is_zero = |a: &Matrix, x: &Matrix, b: &Matrix| {
+            let r = a.mul(x).sub(b).expand();
+            assert_eq!(r, Matrix::zeros(b.rows(), b.cols()), "residual {}", r);
+        };
+        // Needs a row swap: the (0, 0) pivot is zero.
+        let a = matrix(&[&["0", "2", "1"], &["1", "1/2", "0"], &["3", "0", "-1"]]);
+        let b = matrix(&[&["1", "4"], &["2", "0"], &["-1/3", "5"]]);
+        residual_is_zero(&a, &a.solve(&b).unwrap(), &b);
+        let a = matrix(&[&["a", "1"], &["1", "b"]]);
+        let b = matrix(&[&["x"], &["y"]]);
+        let x = a.solve(&b).unwrap();
+        let r = a.mul(&x).sub(&b);
+        for e in r.to_vec() {
+            let (num, _) = e.expand().numer_denom();
+            assert!(num.expand().is_zero(), "residual {}", e);
+        }
+        let xs = a.solve_vec(&[p("x"), p("y")]).unwrap();
+        assert_eq!(xs, x.to_vec());
+        let singular = matrix(&[&["1", "2"], &["2", "4"]]);
+        assert_eq!(singular.solve(&matrix(&[&["1"], &["2"]])).err(), Some(MatrixError::Singular));
+        assert_eq!(
+            a.solve(&matrix(&[&["1"], &["2"], &["3"]])).err(),
+            Some(MatrixError::ShapeMismatch { left: (2, 2), right: (3, 1) })
+        );
+        assert_eq!(
+            matrix(&[&["1", "2"]]).solve_vec(&[p("1")]).err(),
+            Some(MatrixError::NotSquare { rows: 1, cols: 2 })
+        );
+        assert_eq!(
+            a.solve_vec(&[p("1")]).err(),
+            Some(MatrixError::ShapeMismatch { left: (2, 2), right: (1, 1) })
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn grid_layout() {
+        let m = matrix(&[&["x", "1"], &["10", "y + 1"]]);
+        assert_eq!(m.to_string(), "[x,  1]\n[10, 1 + y]");
+        assert_eq!(format!("{}", m), m.to_string());
+        assert_eq!(m.to_pretty_string(80), m.to_string());
+        assert_eq!(matrix(&[&["a"]]).to_string(), "[a]");
+        assert_eq!(m.to_raw_string().trim_end(), "[x, 1]\n[10, 1 + y]");
+    }
+
+    #[wasm_bindgen_test]
+    fn pretty_string_truncation() {
+        let m = matrix(&[&["(x + 1)**2", "y"], &["1", "sin(alpha)"]]);
+        // "(1 + x)**2" and "sin(alpha)" are 10 characters.
+        assert_eq!(m.to_pretty_string(10), m.to_string());
+        assert_eq!(m.to_pretty_string(9), "[(1 + x)*…, y]\n[1,         sin(alph…]");
+        assert_eq!(m.to_pretty_string(4), "[(1 …, y]\n[1,    sin…]");
+        // Widths are capped at one character, the ellipsis itself.
+        assert_eq!(m.to_pretty_string(0), "[…, y]\n[1, …]");
+        assert_eq!(m.to_pretty_string(1), m.to_pretty_string(0));
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_partial_eq() {
+        let a = matrix(&[&["x + 1", "y"], &["0", "z"]]);
+        assert_eq!(a, matrix(&[&["1 + x", "y"], &["0", "z"]]));
+        assert_eq!(a, a.clone());
+        assert_ne!(a, matrix(&[&["x + 1", "y"], &["0", "-z"]]));
+        // Same entries, different shapes.
+        let row = matrix(&[&["1", "2", "3", "4"]]);
+        let col = matrix(&[&["1"], &["2"], &["3"], &["4"]]);
+        let square = matrix(&[&["1", "2"], &["3", "4"]]);
+        assert_ne!(row, col);
+        assert_ne!(row, square);
+        assert!(!row.eq_expanded(&square));
+        assert_eq!(row.transpose(), col);
+        let squared = matrix(&[&["(x + 1)**2", "x*(y - 1)"]]);
+        let expanded = matrix(&[&["x**2 + 2*x + 1", "x*y - x"]]);
+        assert_ne!(squared, expanded);
+        assert!(squared.eq_expanded(&expanded));
+        assert!(expanded.eq_expanded(&squared));
+        assert_eq!(squared.expand(), expanded);
+    }
+
+    #[wasm_bindgen_test]
+    fn matrix_closures() {
+        let m = matrix(&[&["a", "b", "c"], &["d", "e", "f"], &["g", "h", "i"]]);
+        let before = m.clone();
+        let upper = m.map_indexed(|r, c, e| if r > c { Expr::zero() } else { e.clone() });
+        assert_eq!(upper, matrix(&[&["a", "b", "c"], &["0", "e", "f"], &["0", "0", "i"]]));
+        let scaled = m.map_indexed(|r, c, e| if r == c { e.mul(&Expr::integer(2)) } else { e.clone() });
+        assert_eq!(scaled.get(1, 1), p("2*e"));
+        assert_eq!(scaled.get(1, 2), p("f"));
+        let t = Expr::symbol("t");
+        let q = matrix(&[&["t**2", "sin(t)"], &["x", "exp(2*t)"]]);
+        let dq = q.map(|e| e.diff(&t));
+        assert_eq!(dq, matrix(&[&["2*t", "cos(t)"], &["0", "2*exp(2*t)"]]));
+        assert_eq!(dq, q.diff(&t));
+        let mut visited = std::cell::RefCell::new(Vec::new());
+        m.map_indexed(|r, c, e| {
+            visited.borrow_mut().push((r, c));
+            e.clone()
+        });
+        assert_eq!(visited.get_mut().len(), 9);
+        assert_eq!(visited.get_mut()[3], (1, 0));
+        assert_eq!(m, before);
+    }
+
+    #[wasm_bindgen_test]
+    fn dimension_checked_operations() {
+        let a = matrix(&[&["1", "2", "3"], &["4", "5", "6"]]);
+        let b = matrix(&[&["x", "y"], &["z", "w"]]);
+        let still_works = || assert_eq!(b.try_mul(&b).unwrap(), b.mul(&b));
+        let mismatch = |l, r| Err(MatrixError::ShapeMismatch { left: l, right: r });
+        assert_eq!(a.try_mul(&b), mismatch((2, 3), (2, 2)));
+        still_works();
+        assert_eq!(b.try_mul(&a).unwrap().shape(), (2, 3));
+        assert_eq!(a.try_add(&b), mismatch((2, 3), (2, 2)));
+        still_works();
+        assert_eq!(a.try_add(&a.transpose()), mismatch((2, 3), (3, 2)));
+        assert_eq!(a.try_sub(&b), mismatch((2, 3), (2, 2)));
+        still_works();
+        assert_eq!(a.try_inv(), Err(MatrixError::NotSquare { rows: 2, cols: 3 }));
+        still_works();
+        assert_eq!(a.try_det(), Err(MatrixError::NotSquare { rows: 2, cols: 3 }));
+        still_works();
+        assert_eq!(matrix(&[&["1", "1"], &["1", "1"]]).try_inv(), Err(MatrixError::Singular));
+        still_works();
+        assert_eq!(a.try_transpose().unwrap().shape(), (3, 2));
+        assert_eq!(b.try_det().unwrap().expand(), p("x*w - y*z"));
+        assert_eq!(
+            a.try_mul(&b).unwrap_err().to_string(),
+            "incompatible shapes 2x3 and 2x2"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "cannot multiply matrices: incompatible shapes 2x3 and 2x3")]
+    fn mul_mismatch_panics() {
+        let a = matrix(&[&["1", "2", "3"], &["4", "5", "6"]]);
+        a.mul(&a);
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "cannot add matrices: incompatible shapes 1x2 and 2x1")]
+    fn add_mismatch_panics() {
+        let a = matrix(&[&["1", "2"]]);
+        a.add(&a.transpose());
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "cannot invert matrix: expected a square matrix, got 1x2")]
+    fn inv_non_square_panics() {
+        matrix(&[&["1", "2"]]).inv();
+    }
+
+    #[wasm_bindgen_test]
+    #[should_panic(expected = "cannot take determinant: expected a square matrix, got 2x1")]
+    fn det_non_square_panics() {
+        matrix(&[&["1"], &["2"]]).det();
+    }
+
+    #[wasm_bindgen_test]
+    fn sparse_round_trip() {
+        let triplets = vec![(0, 1, p("x")), (2, 0, p("3/4")), (1, 2, p("sin(y)"))];
+        let s = SparseMatrix::from_triplets(3, 4, &triplets).unwrap();
+        assert_eq!(s.shape(), (3, 4));
+        assert_eq!(s.nnz(), 3);
+        assert_eq!(s.get(0, 1), p("x"));
+        assert!(s.get(0, 0).is_zero());
+        // Back out in row-major order.
+        let mut sorted = triplets.clone();
+        sorted.sort_by_key(|&(r, c, _)| (r, c));
+        assert_eq!(s.triplets(), sorted);
+        let dense = s.to_dense();
+        assert_eq!(dense.shape(), (3, 4));
+        for ((r, c), e) in dense.iter_elements() {
+            assert_eq!(e, s.get(r, c), "({}, {})", r, c);
+        }
+        assert_eq!(dense.get(2, 0), p("3/4"));
+        assert_eq!(dense.to_vec().iter().filter(|e| !e.is_zero()).count(), 3);
+    }
+
+    #[wasm_bindgen_test]
+    fn sparse_duplicates_and_bounds() {
+        let triplets = vec![(1, 1, p("x")), (1, 1, p("2*x")), (0, 0, p("1")), (0, 0, p("-1"))];
+        let s = SparseMatrix::from_triplets(2, 2, &triplets).unwrap();
+        assert_eq!(s.get(1, 1), p("3*x"));
+        // Contributions that cancel leave no stored entry.
+        assert!(s.get(0, 0).is_zero());
+        assert_eq!(s.triplets(), [(1, 1, p("3*x"))]);
+        assert_eq!(s.to_dense(), matrix(&[&["0", "0"], &["0", "3*x"]]));
+        assert_eq!(
+            SparseMatrix::from_triplets(2, 3, &[(2, 0, p("1"))]).err(),
+            Some(MatrixError::OutOfRange { index: 2, len: 2 })
+        );
+        assert_eq!(
+            SparseMatrix::from_triplets(2, 3, &[(0, 0, p("1")), (1, 3, p("1"))]).err(),
+            Some(MatrixError::OutOfRange { index: 3, len: 3 })
+        );
+        assert_eq!(s.try_get(0, 2), Err(MatrixError::OutOfRange { index: 2, len: 2 }));
+        assert_eq!(SparseMatrix::new(2, 2).to_dense(), Matrix::zeros(2, 2));
+    }
+}
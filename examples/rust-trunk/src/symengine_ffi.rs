@@ -36,6 +36,12 @@ pub struct CDenseMatrix {
     _opaque: [u8; 0],
 }
 
+/// Opaque sparse matrix (SymEngine's CSRMatrix).
+#[repr(C)]
+pub struct CSparseMatrix {
+    _opaque: [u8; 0],
+}
+
 #[allow(dead_code)]
 extern "C" {
     // =========================================================================
@@ -54,6 +60,7 @@ extern "C" {
     // Construction — parsing & symbols
     // =========================================================================
     pub fn basic_parse(b: *mut BasicStruct, str: *const c_char) -> c_int;
+    pub fn basic_parse2(b: *mut BasicStruct, str: *const c_char, convert_xor: c_int) -> c_int;
     pub fn symbol_set(b: *mut BasicStruct, name: *const c_char) -> c_int;
     pub fn integer_set_si(b: *mut BasicStruct, i: c_long) -> c_int;
     pub fn integer_set_str(b: *mut BasicStruct, c: *const c_char) -> c_int;
@@ -61,6 +68,8 @@ extern "C" {
     pub fn real_double_set_d(b: *mut BasicStruct, d: f64) -> c_int;
     pub fn real_double_get_d(b: *const BasicStruct) -> f64;
     pub fn rational_set_si(b: *mut BasicStruct, i: c_long, j: c_long) -> c_int;
+    pub fn complex_double_real_part(s: *const BasicStruct) -> f64;
+    pub fn complex_double_imaginary_part(s: *const BasicStruct) -> f64;
 
     // =========================================================================
     // Constants
@@ -103,6 +112,7 @@ extern "C" {
     pub fn basic_csc(s: *mut BasicStruct, a: *const BasicStruct) -> c_int;
     pub fn basic_sec(s: *mut BasicStruct, a: *const BasicStruct) -> c_int;
     pub fn basic_cot(s: *mut BasicStruct, a: *const BasicStruct) -> c_int;
+    pub fn basic_atan2(s: *mut BasicStruct, b: *const BasicStruct, a: *const BasicStruct) -> c_int;
 
     // =========================================================================
     // Hyperbolic
@@ -134,6 +144,9 @@ extern "C" {
     pub fn basic_lambertw(s: *mut BasicStruct, a: *const BasicStruct) -> c_int;
     pub fn basic_beta(s: *mut BasicStruct, a: *const BasicStruct, b: *const BasicStruct) -> c_int;
     pub fn basic_polygamma(s: *mut BasicStruct, a: *const BasicStruct, b: *const BasicStruct) -> c_int;
+    pub fn basic_lowergamma(s: *mut BasicStruct, a: *const BasicStruct, b: *const BasicStruct) -> c_int;
+    pub fn basic_uppergamma(s: *mut BasicStruct, a: *const BasicStruct, b: *const BasicStruct) -> c_int;
+    pub fn basic_kronecker_delta(s: *mut BasicStruct, a: *const BasicStruct, b: *const BasicStruct) -> c_int;
 
     // =========================================================================
     // Rounding / sign
@@ -158,17 +171,24 @@ extern "C" {
     // =========================================================================
     pub fn basic_eq(a: *const BasicStruct, b: *const BasicStruct) -> c_int;
     pub fn basic_neq(a: *const BasicStruct, b: *const BasicStruct) -> c_int;
+    pub fn basic_hash(self_: *const BasicStruct) -> usize;
     pub fn number_is_zero(s: *const BasicStruct) -> c_int;
     pub fn number_is_negative(s: *const BasicStruct) -> c_int;
     pub fn number_is_positive(s: *const BasicStruct) -> c_int;
     pub fn number_is_complex(s: *const BasicStruct) -> c_int;
     pub fn basic_has_symbol(e: *const BasicStruct, s: *const BasicStruct) -> c_int;
+    pub fn function_symbol_set(s: *mut BasicStruct, name: *const c_char, args: *const CVecBasic) -> c_int;
+    pub fn function_symbol_get_name(b: *const BasicStruct) -> *mut c_char;
+    pub fn basic_get_args(self_: *const BasicStruct, args: *mut CVecBasic) -> c_int;
+    pub fn basic_get_type(s: *const BasicStruct) -> c_int;
+    pub fn basic_get_class_from_id(id: c_int) -> *mut c_char;
     pub fn is_a_Number(s: *const BasicStruct) -> c_int;
     pub fn is_a_Integer(s: *const BasicStruct) -> c_int;
     pub fn is_a_Rational(s: *const BasicStruct) -> c_int;
     pub fn is_a_Symbol(s: *const BasicStruct) -> c_int;
     pub fn is_a_Complex(s: *const BasicStruct) -> c_int;
     pub fn is_a_RealDouble(s: *const BasicStruct) -> c_int;
+    pub fn is_a_ComplexDouble(s: *const BasicStruct) -> c_int;
 
     // =========================================================================
     // Algebraic
@@ -217,6 +237,7 @@ extern "C" {
     pub fn setbasic_free(self_: *mut CSetBasic);
     pub fn setbasic_insert(self_: *mut CSetBasic, value: *const BasicStruct) -> c_int;
     pub fn setbasic_get(self_: *mut CSetBasic, n: c_int, result: *mut BasicStruct);
+    pub fn setbasic_find(self_: *mut CSetBasic, value: *const BasicStruct) -> c_int;
     pub fn setbasic_size(self_: *mut CSetBasic) -> usize;
 
     // =========================================================================
@@ -225,6 +246,7 @@ extern "C" {
     pub fn mapbasicbasic_new() -> *mut CMapBasicBasic;
     pub fn mapbasicbasic_free(self_: *mut CMapBasicBasic);
     pub fn mapbasicbasic_insert(self_: *mut CMapBasicBasic, key: *const BasicStruct, mapped: *const BasicStruct);
+    pub fn mapbasicbasic_size(self_: *mut CMapBasicBasic) -> usize;
 
     // =========================================================================
     // Free symbols & solving
@@ -237,12 +259,19 @@ extern "C" {
     // =========================================================================
     pub fn basic_add_vec(s: *mut BasicStruct, d: *const CVecBasic) -> c_int;
     pub fn basic_mul_vec(s: *mut BasicStruct, d: *const CVecBasic) -> c_int;
+    pub fn basic_max(s: *mut BasicStruct, d: *const CVecBasic) -> c_int;
+    pub fn basic_min(s: *mut BasicStruct, d: *const CVecBasic) -> c_int;
 
     // =========================================================================
     // Substitution with map
     // =========================================================================
     pub fn basic_subs(s: *mut BasicStruct, e: *const BasicStruct, mapbb: *const CMapBasicBasic) -> c_int;
 
+    // =========================================================================
+    // Common subexpression elimination
+    // =========================================================================
+    pub fn basic_cse(replacement_syms: *mut CVecBasic, replacement_exprs: *mut CVecBasic, reduced_exprs: *mut CVecBasic, exprs: *const CVecBasic) -> c_int;
+
     // =========================================================================
     // Equation solving (linear)
     // =========================================================================
@@ -254,6 +283,7 @@ extern "C" {
     pub fn dense_matrix_new() -> *mut CDenseMatrix;
     pub fn dense_matrix_new_rows_cols(r: c_ulong, c: c_ulong) -> *mut CDenseMatrix;
     pub fn dense_matrix_free(self_: *mut CDenseMatrix);
+    pub fn dense_matrix_set(s: *mut CDenseMatrix, d: *const CDenseMatrix) -> c_int;
     pub fn dense_matrix_set_basic(mat: *mut CDenseMatrix, r: c_ulong, c: c_ulong, s: *mut BasicStruct) -> c_int;
     pub fn dense_matrix_get_basic(s: *mut BasicStruct, mat: *const CDenseMatrix, r: c_ulong, c: c_ulong) -> c_int;
     pub fn dense_matrix_rows(s: *const CDenseMatrix) -> c_ulong;
@@ -264,5 +294,29 @@ extern "C" {
     pub fn dense_matrix_add_matrix(s: *mut CDenseMatrix, a: *const CDenseMatrix, b: *const CDenseMatrix) -> c_int;
     pub fn dense_matrix_mul_matrix(s: *mut CDenseMatrix, a: *const CDenseMatrix, b: *const CDenseMatrix) -> c_int;
     pub fn dense_matrix_mul_scalar(s: *mut CDenseMatrix, a: *const CDenseMatrix, b: *const BasicStruct) -> c_int;
+    pub fn dense_matrix_row_join(a: *mut CDenseMatrix, b: *const CDenseMatrix) -> c_int;
+    pub fn dense_matrix_col_join(a: *mut CDenseMatrix, b: *const CDenseMatrix) -> c_int;
+    pub fn dense_matrix_row_del(a: *mut CDenseMatrix, k: c_ulong) -> c_int;
+    pub fn dense_matrix_col_del(a: *mut CDenseMatrix, k: c_ulong) -> c_int;
+    pub fn dense_matrix_jacobian(s: *mut CDenseMatrix, a: *const CDenseMatrix, x: *const CDenseMatrix) -> c_int;
+    pub fn dense_matrix_LU(l: *mut CDenseMatrix, u: *mut CDenseMatrix, mat: *const CDenseMatrix) -> c_int;
+    pub fn dense_matrix_LDL(l: *mut CDenseMatrix, d: *mut CDenseMatrix, mat: *const CDenseMatrix) -> c_int;
+    pub fn dense_matrix_FFLU(lu: *mut CDenseMatrix, mat: *const CDenseMatrix) -> c_int;
+    pub fn dense_matrix_FFLDU(l: *mut CDenseMatrix, d: *mut CDenseMatrix, u: *mut CDenseMatrix, mat: *const CDenseMatrix) -> c_int;
+    pub fn dense_matrix_LU_solve(x: *mut CDenseMatrix, a: *const CDenseMatrix, b: *const CDenseMatrix) -> c_int;
+    pub fn dense_matrix_diff(s: *mut CDenseMatrix, a: *const CDenseMatrix, x: *const BasicStruct) -> c_int;
+    pub fn dense_matrix_eq(lhs: *const CDenseMatrix, rhs: *const CDenseMatrix) -> c_int;
     pub fn dense_matrix_str(s: *const CDenseMatrix) -> *mut c_char;
+
+    // =========================================================================
+    // Sparse matrix (CSR)
+    // =========================================================================
+    pub fn sparse_matrix_new() -> *mut CSparseMatrix;
+    pub fn sparse_matrix_free(self_: *mut CSparseMatrix);
+    pub fn sparse_matrix_init(s: *mut CSparseMatrix) -> c_int;
+    pub fn sparse_matrix_rows_cols(s: *mut CSparseMatrix, r: c_ulong, c: c_ulong) -> c_int;
+    pub fn sparse_matrix_get_basic(s: *mut BasicStruct, mat: *const CSparseMatrix, r: c_ulong, c: c_ulong) -> c_int;
+    pub fn sparse_matrix_set_basic(mat: *mut CSparseMatrix, r: c_ulong, c: c_ulong, s: *mut BasicStruct) -> c_int;
+    pub fn sparse_matrix_str(s: *const CSparseMatrix) -> *mut c_char;
+    pub fn sparse_matrix_eq(lhs: *mut CSparseMatrix, rhs: *mut CSparseMatrix) -> c_int;
 }